@@ -46,7 +46,7 @@ let
         ${initNix}
         # This is what nixpkgs-vet uses
         export NIXPKGS_VET_NIX_PACKAGE=${lib.getBin nix}
-        ${nixpkgs-vet}/bin/.nixpkgs-vet-wrapped --base "${nixpkgs}" "${nixpkgs}"
+        ${nixpkgs-vet}/bin/.nixpkgs-vet-wrapped "${nixpkgs}"
         touch $out
       '';
 in
@@ -0,0 +1,364 @@
+//! The project configuration, read from an optional `nixpkgs-vet.toml` file in the root of the
+//! Nixpkgs to check.
+//!
+//! This allows Nixpkgs to adjust policies without needing a new nixpkgs-vet release. An example:
+//!
+//! ```toml
+//! [checks]
+//! # Problems with these codes are not reported
+//! disabled = ["NPV-170"]
+//!
+//! [checks.severity]
+//! # Problems with these codes are reported, but only errors fail the validation
+//! NPV-145 = "warning"
+//!
+//! [files]
+//! # Nix files under these paths are not checked
+//! ignored-paths = ["pkgs/test/nixpkgs-vet"]
+//!
+//! [by-name]
+//! # Further directories with the same structure as `pkgs/by-name`
+//! extra-roots = ["pkgs/development/python-modules/by-name"]
+//! # Files allowed directly in `pkgs/by-name` instead of shard directories
+//! root-files = ["README.md"]
+//! # Root files that must exist and not be empty
+//! required-root-files = ["README.md"]
+//! # The maximum size of files in package directories in bytes
+//! max-file-size = 1048576
+//!
+//! [ratchets]
+//! # Whether to enforce the individual ratchet checks
+//! uses-by-name = true
+//! strict-deps = true
+//! structured-attrs = false
+//! package-name = true
+//! alias-arguments = true
+//! fetcher-hashes = true
+//! final-attrs = true
+//! deprecated-attributes = true
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::Deserialize;
+
+use crate::problem::{self, Category, Check, Problem, Severity};
+use crate::validation::{self, Validation};
+
+pub const CONFIG_FILENAME: &str = "nixpkgs-vet.toml";
+
+/// Problem codes that can't be disabled or made non-errors, because other checks rely on them to
+/// succeed.
+const REQUIRED_CODES: &[&str] = &[
+    "NPV-100", "NPV-101", "NPV-109", "NPV-120", "NPV-140", "NPV-147",
+];
+
+/// Ratchet checks that only compare the results of the file checks, so they don't need the
+/// evaluation, unlike the other ratchet checks.
+const FILE_RATCHET_CODES: &[&str] = &["NPV-173", "NPV-174", "NPV-175"];
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub checks: Checks,
+    pub files: Files,
+    pub by_name: ByName,
+    pub ratchets: Ratchets,
+    /// The checks selected on the command line, not part of the config file.
+    #[serde(skip)]
+    pub selection: Selection,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Checks {
+    /// The codes of problems that shouldn't be reported.
+    pub disabled: BTreeSet<String>,
+    /// Overrides for the severity of problems by their code.
+    pub severity: BTreeMap<String, Severity>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Files {
+    /// Paths relative to the Nixpkgs root whose Nix files shouldn't be checked by
+    /// `files::check_files`.
+    pub ignored_paths: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ByName {
+    /// Paths relative to the Nixpkgs root of directories structured like `pkgs/by-name`.
+    ///
+    /// Only the structure and references are checked for these, not the evaluation, since their
+    /// packages aren't top-level attributes.
+    pub extra_roots: Vec<String>,
+    /// Names of files allowed directly in `pkgs/by-name` and the extra roots, where otherwise only
+    /// shard directories are allowed.
+    pub root_files: BTreeSet<String>,
+    /// Names of root files that must exist and not be empty. These are allowed even if they're
+    /// not in `root_files`.
+    pub required_root_files: BTreeSet<String>,
+    /// The maximum size in bytes of each file in a package directory.
+    pub max_file_size: u64,
+}
+
+impl Default for ByName {
+    fn default() -> Self {
+        Self {
+            extra_roots: vec![],
+            root_files: BTreeSet::from(["README.md".to_owned()]),
+            required_root_files: BTreeSet::new(),
+            max_file_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Ratchets {
+    /// Whether new top-level packages need to use `pkgs/by-name`.
+    pub uses_by_name: bool,
+    /// Whether new top-level packages need to enable `strictDeps`.
+    pub strict_deps: bool,
+    /// Whether new top-level packages need to enable `__structuredAttrs`.
+    pub structured_attrs: bool,
+    /// Whether new packages in `pkgs/by-name` need a `pname` matching their attribute name.
+    pub package_name: bool,
+    /// Whether new packages in `pkgs/by-name` must not take aliases as arguments.
+    pub alias_arguments: bool,
+    /// Whether new Nix files need to pass real SRI hashes to fetchers.
+    pub fetcher_hashes: bool,
+    /// Whether new Nix files need to create derivations with `finalAttrs:` instead of `rec`.
+    pub final_attrs: bool,
+    /// Whether new Nix files must not use deprecated attributes.
+    pub deprecated_attributes: bool,
+}
+
+impl Default for Ratchets {
+    fn default() -> Self {
+        Self {
+            uses_by_name: true,
+            strict_deps: true,
+            structured_attrs: true,
+            package_name: true,
+            alias_arguments: true,
+            fetcher_hashes: true,
+            final_attrs: true,
+            deprecated_attributes: true,
+        }
+    }
+}
+
+/// The checks selected with `--only` and `--skip`.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    /// If not empty, only these checks are run.
+    pub only: Vec<Selector>,
+    /// These checks are not run.
+    pub skip: Vec<Selector>,
+}
+
+/// A check category or a single problem code, as passed to `--only` and `--skip`.
+#[derive(Clone, Debug)]
+pub enum Selector {
+    Category(Category),
+    Code(&'static str),
+}
+
+impl Selector {
+    fn matches(&self, check: &Check) -> bool {
+        match self {
+            Self::Category(category) => check.category == *category,
+            Self::Code(code) => check.code == *code,
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let category = match s {
+            "structure" => Category::Structure,
+            "references" => Category::References,
+            "eval" | "evaluation" => Category::Evaluation,
+            "ratchet" => Category::Ratchet,
+            "files" => Category::Files,
+            _ => {
+                return problem::check(s)
+                    .map(|check| Self::Code(check.code))
+                    .ok_or_else(|| {
+                        "expected one of structure, references, eval, ratchet, files or a \
+                         problem code like NPV-100, see `nixpkgs-vet list-checks`"
+                            .to_owned()
+                    });
+            }
+        };
+        Ok(Self::Category(category))
+    }
+}
+
+impl Selection {
+    /// Whether a check is selected.
+    fn includes(&self, check: &Check) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|selector| selector.matches(check)))
+            && !self.skip.iter().any(|selector| selector.matches(check))
+    }
+}
+
+impl Config {
+    /// Reads the configuration file from the root of a Nixpkgs, falling back to the default
+    /// configuration if there is none.
+    pub fn load(nixpkgs_path: &Path) -> anyhow::Result<Config> {
+        let path = nixpkgs_path.join(CONFIG_FILENAME);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Could not read config file {}", path.display()))?;
+        let config: Config = toml::from_str(&contents)
+            .with_context(|| format!("Could not parse config file {}", path.display()))?;
+
+        if let Some(code) = config
+            .checks
+            .disabled
+            .iter()
+            .chain(config.checks.severity.keys())
+            // Codes are looked up verbatim, so they need to match exactly
+            .find(|code| !problem::CHECKS.iter().any(|check| check.code == *code))
+        {
+            anyhow::bail!(
+                "Config file {} refers to the unknown problem code {code}, see \
+                 `nixpkgs-vet list-checks`",
+                path.display()
+            );
+        }
+
+        if let Some(code) = REQUIRED_CODES
+            .iter()
+            .find(|code| config.checks.disabled.contains(**code))
+        {
+            anyhow::bail!(
+                "Config file {} disables {code}, which other checks rely on and can't be disabled",
+                path.display()
+            );
+        }
+
+        if let Some(code) = REQUIRED_CODES.iter().find(|code| {
+            config
+                .checks
+                .severity
+                .get(**code)
+                .is_some_and(|severity| *severity != Severity::Error)
+        }) {
+            anyhow::bail!(
+                "Config file {} lowers the severity of {code}, which other checks rely on and \
+                 must stay an error",
+                path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Whether a problem should be reported.
+    pub fn is_enabled(&self, problem: &Problem) -> bool {
+        let code = problem.npv_code();
+        !self.checks.disabled.contains(code)
+            && (REQUIRED_CODES.contains(&code)
+                || problem::check(code).is_none_or(|check| self.selection.includes(check)))
+    }
+
+    /// Whether the checks of a category need to run. Categories other checks depend on also
+    /// run if those do.
+    pub fn runs(&self, category: Category) -> bool {
+        let selected_where = |matches: &dyn Fn(&Check) -> bool| {
+            problem::CHECKS.iter().any(|check| {
+                matches(check)
+                    && !self.checks.disabled.contains(check.code)
+                    && self.selection.includes(check)
+            })
+        };
+        let selected = |category| selected_where(&|check| check.category == category);
+        match category {
+            // Evaluation needs the package names from the structure check
+            Category::Structure => {
+                selected(Category::Structure)
+                    || selected(Category::References)
+                    || self.runs(Category::Evaluation)
+            }
+            // Package ratchets need the evaluation results, unless the evaluation is skipped
+            // explicitly, in which case there are no packages to compare
+            Category::Evaluation => {
+                selected(Category::Evaluation)
+                    || (selected_where(&|check| {
+                        check.category == Category::Ratchet
+                            && !FILE_RATCHET_CODES.contains(&check.code)
+                    }) && !self.selection.skip.iter().any(|selector| {
+                        matches!(selector, Selector::Category(Category::Evaluation))
+                    }))
+            }
+            Category::Ratchet => selected(Category::Ratchet),
+            // File ratchets need the file results
+            Category::Files => selected(Category::Files) || self.runs(Category::Ratchet),
+            Category::References => selected(category),
+        }
+    }
+
+    /// Returns the severity of a problem, taking the configured overrides into account.
+    pub fn severity(&self, problem: &Problem) -> Severity {
+        self.checks
+            .severity
+            .get(problem.npv_code())
+            .copied()
+            .unwrap_or_else(|| problem.severity())
+    }
+
+    /// Removes the problems of disabled checks from a validation and applies the configured
+    /// severities, so that the validation only fails for errors.
+    pub fn filter(&self, validation: Validation<()>) -> Validation<()> {
+        let problems = match validation {
+            Validation::Success(()) => return Validation::Success(()),
+            Validation::Failure(problems) | Validation::SuccessWithWarnings((), problems) => {
+                problems
+            }
+        };
+        validation::with_problems(
+            problems
+                .into_iter()
+                .filter(|problem| self.is_enabled(problem))
+                .map(|problem| {
+                    let severity = self.severity(&problem);
+                    problem.with_severity(severity)
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether the Nix files under a path relative to the Nixpkgs root should be checked.
+    pub fn is_ignored(&self, path: &RelativePath) -> bool {
+        self.files
+            .ignored_paths
+            .iter()
+            .any(|ignored| path.starts_with(ignored))
+    }
+
+    /// Whether a file is allowed directly in a `pkgs/by-name`-like directory.
+    pub fn is_root_file(&self, file_name: &str) -> bool {
+        self.by_name.root_files.contains(file_name)
+            || self.by_name.required_root_files.contains(file_name)
+    }
+
+    /// The `pkgs/by-name`-like directories in addition to `pkgs/by-name` itself.
+    pub fn extra_by_name_roots(&self) -> impl Iterator<Item = RelativePathBuf> {
+        self.by_name.extra_roots.iter().map(RelativePathBuf::from)
+    }
+}
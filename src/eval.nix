@@ -1,8 +1,13 @@
-# Takes a path to nixpkgs and a path to the json-encoded list of `pkgs/by-name` attributes.
+# Takes a path to nixpkgs, a path to the json-encoded list of `pkgs/by-name` attributes and whether
+# to compute the aliases, which is only needed for the alias arguments ratchet.
 #
 # Returns a value containing information on all Nixpkgs attributes which is decoded on the Rust
 # side. See ./eval.rs for the meaning of the returned values.
-{ attrsPath, nixpkgsPath }:
+{
+  attrsPath,
+  nixpkgsPath,
+  computeAliases,
+}:
 let
   attrs = builtins.fromJSON (builtins.readFile attrsPath);
 
@@ -33,6 +38,36 @@ let
     system = "x86_64-linux";
   };
 
+  # Nixpkgs without aliases, like it's evaluated in CI, to find out which attributes are aliases.
+  # This evaluates Nixpkgs a second time, so it's only used if `computeAliases` is set.
+  pkgsWithoutAliases = import nixpkgsPath {
+    config = {
+      allowAliases = false;
+    };
+    system = "x86_64-linux";
+  };
+
+  # The GitHub handles (or names) of the maintainers of a package, for `nixpkgs-vet stats`.
+  # Broken `meta` attributes must not affect the other results, so failures are caught here.
+  maintainersOf =
+    value:
+    let
+      maintainers = builtins.filter builtins.isString (
+        map (maintainer: maintainer.github or maintainer.name or null) (value.meta.maintainers or [ ])
+      );
+      result = builtins.tryEval (builtins.deepSeq maintainers maintainers);
+    in
+    if result.success then result.value else [ ];
+
+  # The value of a string attribute like `pname`, or null if it doesn't exist, isn't a string or
+  # fails to evaluate. Used for the ratchet check that the `pname` matches the attribute name.
+  stringAttrOf =
+    attr: value:
+    let
+      result = builtins.tryEval (value.${attr} or null);
+    in
+    if result.success && builtins.isString result.value then result.value else null;
+
   # See AttributeInfo in ./eval.rs for the meaning of this.
   attrInfo = name: value: {
     location = builtins.unsafeGetAttrPos name pkgs;
@@ -69,6 +104,9 @@ let
             strict_deps = cleanPackage.strictDeps or false;
             structured_attrs = cleanPackage.__structuredAttrs or false;
             is_same_scope_call_package = value._callPackage or false;
+            maintainers = maintainersOf value;
+            pname = stringAttrOf "pname" value;
+            name = stringAttrOf "name" value;
           };
         };
   };
@@ -103,9 +141,19 @@ let
   # All attributes
   attributes = byNameAttrs // nonByNameAttrs;
 in
-# We output them in the form [ [ <name> <value> ] ]` such that the Rust side doesn't need to sort
-# them again to get deterministic behavior. This is good for testing.
-map (name: [
-  name
-  attributes.${name}
-]) (builtins.attrNames attributes)
+{
+  # We output them in the form [ [ <name> <value> ] ]` such that the Rust side doesn't need to sort
+  # them again to get deterministic behavior. This is good for testing.
+  attributes = map (name: [
+    name
+    attributes.${name}
+  ]) (builtins.attrNames attributes);
+
+  # The names of all attributes that only exist as aliases, or null if they're not needed. Only the
+  # names are needed, so the values aren't evaluated.
+  aliases =
+    if computeAliases then
+      builtins.attrNames (builtins.removeAttrs pkgs (builtins.attrNames pkgsWithoutAliases))
+    else
+      null;
+}
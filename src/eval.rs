@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 use std::{env, fs, process};
 
@@ -6,16 +6,30 @@ use anyhow::Context;
 use relative_path::RelativePathBuf;
 use serde::Deserialize;
 
-use crate::problem::{npv_100, npv_101, npv_120};
+use crate::config::Config;
+use crate::problem::{npv_100, npv_101, npv_105, npv_120};
 use crate::ratchet::RatchetState::{Loose, Tight};
 use crate::structure::BASE_SUBPATH;
 use crate::validation::ResultIteratorExt as _;
-use crate::validation::{self, Validation::Success};
+use crate::validation::{
+    self,
+    Validation::{Failure, Success},
+};
 use crate::{NixFileStore, structure};
 use crate::{location, ratchet};
 
 const EVAL_NIX: &[u8] = include_bytes!("eval.nix");
 
+/// The value returned by `./eval.nix`
+#[derive(Deserialize)]
+struct EvalResult {
+    /// All attributes, sorted by name.
+    attributes: Vec<(String, Attribute)>,
+    /// The names of the attributes that only exist as aliases, if the alias arguments ratchet is
+    /// enabled, since finding them requires evaluating Nixpkgs a second time.
+    aliases: Option<BTreeSet<String>>,
+}
+
 /// Attribute set of this structure is returned by `./eval.nix`
 #[derive(Deserialize)]
 enum Attribute {
@@ -84,6 +98,12 @@ pub enum AttributeVariant {
         structured_attrs: bool,
         /// Whether the attribute was defined via `callPackage` of the same scope
         is_same_scope_call_package: bool,
+        /// The GitHub handles or names of the maintainers in `meta.maintainers`.
+        maintainers: Vec<String>,
+        /// The `pname` attribute, if it's a string.
+        pname: Option<String>,
+        /// The `name` attribute, if it's a string.
+        name: Option<String>,
     },
 }
 
@@ -145,6 +165,7 @@ fn mutate_nix_instatiate_arguments_based_on_cfg(
 /// The validation result is a map from package names to a package ratchet state.
 pub fn check_values(
     nixpkgs_path: &Path,
+    config: &Config,
     nix_file_store: &mut NixFileStore,
     package_names: &[String],
 ) -> validation::Result<BTreeMap<String, ratchet::Package>> {
@@ -198,7 +219,9 @@ pub fn check_values(
         .args(["--arg", "nixpkgsPath"])
         .arg(nixpkgs_path)
         .arg("-I")
-        .arg(nixpkgs_path);
+        .arg(nixpkgs_path)
+        .args(["--arg", "computeAliases"])
+        .arg(config.ratchets.alias_arguments.to_string());
 
     pass_through_environment_variables_for_nix_eval_in_nix_build(&mut command);
     mutate_nix_instatiate_arguments_based_on_cfg(&work_dir_path, &mut command)?;
@@ -211,17 +234,21 @@ pub fn check_values(
 
     if !result.status.success() {
         // Early return in case evaluation fails
-        return Ok(npv_120::NixEvalError::new(String::from_utf8_lossy(&result.stderr)).into());
+        return Ok(Failure(vec![
+            npv_120::NixEvalError::new(String::from_utf8_lossy(&result.stderr)).into(),
+        ]));
     }
 
     // Parse the resulting JSON value
-    let attributes: Vec<(String, Attribute)> = serde_json::from_slice(&result.stdout)
-        .with_context(|| {
-            format!(
-                "Failed to deserialise {}",
-                String::from_utf8_lossy(&result.stdout)
-            )
-        })?;
+    let EvalResult {
+        attributes,
+        aliases,
+    } = serde_json::from_slice(&result.stdout).with_context(|| {
+        format!(
+            "Failed to deserialise {}",
+            String::from_utf8_lossy(&result.stdout)
+        )
+    })?;
 
     let check_result = validation::sequence(
         attributes
@@ -234,9 +261,14 @@ pub fn check_values(
                         &attribute_name,
                         non_by_name_attribute,
                     )?,
-                    Attribute::ByName(by_name_attribute) => {
-                        by_name(&attribute_name, by_name_attribute)?
-                    }
+                    Attribute::ByName(by_name_attribute) => by_name(
+                        nixpkgs_path,
+                        config,
+                        nix_file_store,
+                        aliases.as_ref(),
+                        &attribute_name,
+                        by_name_attribute,
+                    )?,
                 };
                 Ok::<_, anyhow::Error>(check_result.map(|value| (attribute_name.clone(), value)))
             })
@@ -248,6 +280,10 @@ pub fn check_values(
 
 /// Handle the evaluation result for an attribute in `pkgs/by-name`, making it a validation result.
 fn by_name(
+    nixpkgs_path: &Path,
+    config: &Config,
+    nix_file_store: &mut NixFileStore,
+    aliases: Option<&BTreeSet<String>>,
     attribute_name: &str,
     by_name_attribute: ByNameAttribute,
 ) -> validation::Result<ratchet::Package> {
@@ -258,7 +294,9 @@ fn by_name(
         ByNameAttribute::Missing => {
             // This indicates a bug in the `pkgs/by-name` overlay, because it's supposed to
             // automatically defined attributes in `pkgs/by-name`
-            npv_100::ByNameUndefinedAttribute::new(attribute_name).into()
+            Failure(vec![
+                npv_100::ByNameUndefinedAttribute::new(attribute_name).into(),
+            ])
         }
         // The attribute exists
         ByNameAttribute::Existing(AttributeInfo {
@@ -272,7 +310,9 @@ fn by_name(
             //
             // We can't know whether the attribute is automatically or manually defined for sure,
             // and while we could check the location, the error seems clear enough as is.
-            npv_101::ByNameNonDerivation::new(attribute_name).into()
+            Failure(vec![
+                npv_101::ByNameNonDerivation::new(attribute_name).into(),
+            ])
         }
         // The attribute exists
         ByNameAttribute::Existing(AttributeInfo {
@@ -283,12 +323,37 @@ fn by_name(
                     strict_deps,
                     structured_attrs,
                     is_same_scope_call_package: _,
+                    maintainers,
+                    pname,
+                    name,
                 },
-            location: _,
+            location,
         }) => {
             // Only derivations are allowed in `pkgs/by-name`.
-            if is_derivation {
-                Success(ratchet::Package {
+            if !is_derivation {
+                Failure(vec![
+                    npv_101::ByNameNonDerivation::new(attribute_name).into(),
+                ])
+            } else {
+                let redundant_override = match location {
+                    Some(location) => redundant_by_name_override(
+                        nixpkgs_path,
+                        nix_file_store,
+                        attribute_name,
+                        location,
+                    )?,
+                    None => None,
+                };
+                // NPV-100 and NPV-101 above can't be disabled or made non-errors, but this can.
+                let result = match redundant_override {
+                    Some(problem) => config.filter(problem.into()),
+                    None => Success(()),
+                };
+                let alias_arguments =
+                    alias_arguments_ratchet(nixpkgs_path, nix_file_store, aliases, attribute_name)?;
+                result.map(|()| ratchet::Package {
+                    file: Some(structure::relative_file_for_package(attribute_name)),
+                    maintainers,
                     uses_by_name: Tight,
                     strict_deps: enabled_attribute_ratchet(
                         strict_deps,
@@ -298,15 +363,132 @@ fn by_name(
                         structured_attrs,
                         structure::relative_file_for_package(attribute_name),
                     ),
+                    package_name: package_name_ratchet(attribute_name, pname, name),
+                    alias_arguments,
                 })
-            } else {
-                npv_101::ByNameNonDerivation::new(attribute_name).into()
             }
         }
     };
     Ok(result)
 }
 
+/// Returns the ratchet state for whether the `pname` of a `pkgs/by-name` attribute matches its
+/// name, falling back to the package name part of `name` if there's no `pname`.
+fn package_name_ratchet(
+    attribute_name: &str,
+    pname: Option<String>,
+    name: Option<String>,
+) -> ratchet::RatchetState<ratchet::PackageName> {
+    use ratchet::RatchetState::NonApplicable;
+
+    let Some(pname) = pname.or_else(|| name.map(|name| parse_drv_name(&name).to_owned())) else {
+        return NonApplicable;
+    };
+    if normalise_package_name(&pname) == normalise_package_name(attribute_name) {
+        Tight
+    } else {
+        Loose((pname, structure::relative_file_for_package(attribute_name)))
+    }
+}
+
+/// Returns the ratchet state for whether the `package.nix` of a `pkgs/by-name` attribute takes
+/// arguments that are only defined as aliases. Since `callPackage` passes arguments by name, this
+/// can be determined from the formal arguments of the function. Without `aliases`, because the
+/// ratchet is disabled, this is not applicable.
+fn alias_arguments_ratchet(
+    nixpkgs_path: &Path,
+    nix_file_store: &mut NixFileStore,
+    aliases: Option<&BTreeSet<String>>,
+    attribute_name: &str,
+) -> anyhow::Result<ratchet::RatchetState<ratchet::AliasArguments>> {
+    use ratchet::RatchetState::NonApplicable;
+
+    let Some(aliases) = aliases else {
+        return Ok(NonApplicable);
+    };
+
+    let relative_package_file = structure::relative_file_for_package(attribute_name);
+    let Some(nix_file) = nix_file_store.get(&relative_package_file.to_path(nixpkgs_path))? else {
+        // NPV-147 is reported for this already.
+        return Ok(NonApplicable);
+    };
+    let Some(formals) = nix_file.formals() else {
+        return Ok(NonApplicable);
+    };
+
+    let alias_arguments: Vec<String> = formals
+        .into_iter()
+        .map(|formal| formal.name)
+        .filter(|name| aliases.contains(name))
+        .collect();
+    Ok(if alias_arguments.is_empty() {
+        Tight
+    } else {
+        Loose((alias_arguments, relative_package_file))
+    })
+}
+
+/// Returns the package name part of a derivation name like `foo-bar-1.0`, which ends at the first
+/// `-` not followed by a letter, just like `builtins.parseDrvName`.
+fn parse_drv_name(name: &str) -> &str {
+    name.char_indices()
+        .find(|&(index, char)| {
+            char == '-'
+                && name[index + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| !next.is_ascii_alphabetic())
+        })
+        .map_or(name, |(index, _)| &name[..index])
+}
+
+/// Package names are allowed to differ from attribute names in case and in the separators `-`,
+/// `_` and `.`, since `.` isn't allowed in attribute names in `pkgs/by-name`.
+fn normalise_package_name(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Checks whether the manual definition of an attribute in `pkgs/by-name` is redundant, which is
+/// the case for `<attr> = callPackage ../by-name/<shard>/<attr>/package.nix { }`.
+///
+/// Attributes that are only defined automatically don't have a location, because the `pkgs/by-name`
+/// overlay uses `mapAttrs`, so this is only called for manual definitions.
+fn redundant_by_name_override(
+    nixpkgs_path: &Path,
+    nix_file_store: &mut NixFileStore,
+    attribute_name: &str,
+    location: Location,
+) -> anyhow::Result<Option<npv_105::ByNameOverrideContainsEmptyArgument>> {
+    // Parse the Nix file in the location
+    let Some(nix_file) = nix_file_store.get(&location.file)? else {
+        // NPV-147 is reported for this already.
+        return Ok(None);
+    };
+
+    // The relative location of the Nix file, for error messages
+    let location = location.relative(nixpkgs_path).with_context(|| {
+        format!("Failed to resolve the file where attribute {attribute_name} is defined")
+    })?;
+
+    let (optional_call_package, _definition) = nix_file
+        .call_package_argument_info_at(location.line, location.column, nixpkgs_path)
+        .with_context(|| {
+            format!("Failed to get the definition info for attribute {attribute_name}")
+        })?;
+
+    // Only definitions calling the package's own file without any arguments are redundant. Other
+    // paths are allowed for compatibility, and arguments are what overrides are for.
+    let is_redundant = optional_call_package.is_some_and(|call_package| {
+        call_package.relative_path == Some(structure::relative_file_for_package(attribute_name))
+            && call_package
+                .overrides
+                .is_some_and(|overrides| overrides.is_empty())
+    });
+
+    Ok(is_redundant
+        .then(|| npv_105::ByNameOverrideContainsEmptyArgument::new(attribute_name, location)))
+}
+
 fn enabled_attribute_ratchet<R>(enabled: bool, file: RelativePathBuf) -> ratchet::RatchetState<R>
 where
     R: ratchet::ToProblem<ToContext = RelativePathBuf>,
@@ -353,11 +535,17 @@ fn handle_non_by_name_attribute(
                     strict_deps,
                     structured_attrs,
                     is_same_scope_call_package,
+                    maintainers,
+                    pname: _,
+                    name: _,
                 },
             location: Some(location),
         }) => {
             // Parse the Nix file in the location
-            let nix_file = nix_file_store.get(&location.file)?;
+            let Some(nix_file) = nix_file_store.get(&location.file)? else {
+                // NPV-147 is reported for this already, so the ratchet state doesn't matter.
+                return Ok(Success(non_applicable_package()));
+            };
 
             // The relative location of the Nix file, for error messages
             let location = location.relative(nixpkgs_path).with_context(|| {
@@ -425,24 +613,55 @@ fn handle_non_by_name_attribute(
                 (false, file) => Loose(file),
             };
 
-            let structured_attrs = match (structured_attrs, evaluated_attribute_file) {
+            let structured_attrs = match (structured_attrs, evaluated_attribute_file.clone()) {
                 (true, _) => Tight,
                 (false, file) => Loose(file),
             };
 
             ratchet::Package {
+                file: Some(evaluated_attribute_file),
+                maintainers,
                 uses_by_name,
                 strict_deps,
                 structured_attrs,
+                // Only packages in `pkgs/by-name` need to match their attribute name.
+                package_name: NonApplicable,
+                // The arguments of packages outside `pkgs/by-name` can come from anywhere.
+                alias_arguments: NonApplicable,
             }
         }
         // This catches all the cases not matched by the above `EvalSuccess`, falling back to not
         // being able to make any good calls about the ratchet state.
-        _ => ratchet::Package {
-            uses_by_name: NonApplicable,
-            strict_deps: NonApplicable,
-            structured_attrs: NonApplicable,
-        },
+        _ => non_applicable_package(),
     };
     Ok(Success(package))
 }
+
+/// The ratchet state of a package outside `pkgs/by-name` that no ratchet applies to.
+fn non_applicable_package() -> ratchet::Package {
+    use ratchet::RatchetState::NonApplicable;
+
+    ratchet::Package {
+        file: None,
+        maintainers: vec![],
+        uses_by_name: NonApplicable,
+        strict_deps: NonApplicable,
+        structured_attrs: NonApplicable,
+        package_name: NonApplicable,
+        alias_arguments: NonApplicable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_names() {
+        assert_eq!(parse_drv_name("foo-bar-1.0"), "foo-bar");
+        assert_eq!(parse_drv_name("foo-unstable-2024-01-01"), "foo-unstable");
+        assert_eq!(parse_drv_name("foo"), "foo");
+        assert_eq!(parse_drv_name("foo-"), "foo-");
+        assert_eq!(normalise_package_name("Foo_bar.Baz"), "foo-bar-baz");
+    }
+}
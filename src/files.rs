@@ -1,62 +1,108 @@
 use relative_path::RelativePath;
 use relative_path::RelativePathBuf;
+use rnix::SyntaxKind;
 use rnix::ast;
 use rnix::ast::AstToken;
+use rnix::ast::HasEntry;
 use rowan::ast::AstNode;
+use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::fs;
 use std::io::Read;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use std::sync::LazyLock;
 
+use crate::config::Config;
 use crate::location;
-use crate::nix_file::{NixFile, NixFileStore};
-use crate::problem::{Problem, npv_145, npv_146, npv_170};
+use crate::nix_file::{self, NixFile, NixFileStore};
+use crate::problem::npv_173::{FetcherHash, FetcherHashKind};
+use crate::problem::npv_174::RecDerivation;
+use crate::problem::npv_175::DeprecatedAttributeUse;
+use crate::problem::{self, Problem, npv_145, npv_146, npv_170, npv_171, npv_172};
 use crate::validation::ResultIteratorExt;
-use crate::validation::Validation::{Failure, Success};
+use crate::validation::Validation::Success;
 use crate::validation::sequence_;
 use crate::{ratchet, structure, validation};
 
 /// Runs check on all Nix files, returning a ratchet result for each
 pub fn check_files(
     nixpkgs_path: &Path,
+    config: &Config,
     nix_file_store: &mut NixFileStore,
 ) -> validation::Result<BTreeMap<RelativePathBuf, ratchet::File>> {
-    process_nix_files(nixpkgs_path, nix_file_store, |relative_path, nix_file| {
-        let result = sequence_([
-            check_executable_iff_shebang(relative_path, &nix_file.path)?,
-            check_invalid_escapes(relative_path, nix_file)?,
-        ]);
-        Ok(result.map(|()| ratchet::File {}))
-    })
+    let nix_files_result = process_nix_files(
+        nixpkgs_path,
+        config,
+        nix_file_store,
+        |relative_path, nix_file| {
+            let result = sequence_([
+                check_executable_iff_shebang(relative_path, &nix_file.path)?,
+                check_invalid_escapes(relative_path, nix_file)?,
+                // This needs to come last, so that all other checks had a chance to use the
+                // suppressions. Note that `references::check_references` also uses them, which runs
+                // before this in `check_nixpkgs`.
+                check_suppressions(relative_path, nix_file, config),
+            ]);
+            Ok(config.filter(result).map(|()| ratchet::File {
+                fetcher_hashes: check_fetcher_hashes(nix_file),
+                final_attrs: check_rec_derivations(nix_file),
+                deprecated_attributes: check_deprecated_attributes(nix_file),
+            }))
+        },
+    )?;
+
+    // Files without a `.nix` extension are only parsed if the references check follows an
+    // `import` or `callPackage` to them, which honours their suppressions too
+    let imported_result = sequence_(
+        nix_file_store
+            .parsed_files()
+            .into_iter()
+            .filter(|nix_file| nix_file.path.extension().is_none_or(|x| x != "nix"))
+            .filter_map(|nix_file| {
+                let relative_path =
+                    RelativePathBuf::from_path(nix_file.path.strip_prefix(nixpkgs_path).ok()?)
+                        .ok()?;
+                (!config.is_ignored(&relative_path))
+                    .then(|| config.filter(check_suppressions(&relative_path, nix_file, config)))
+            }),
+    );
+
+    Ok(nix_files_result.and(imported_result, |files, ()| files))
 }
 
 /// Processes all Nix files in a Nixpkgs directory according to a given function `f`, collecting the
 /// results into a mapping from each file to a ratchet value.
 fn process_nix_files(
     nixpkgs_path: &Path,
+    config: &Config,
     nix_file_store: &mut NixFileStore,
     f: impl Fn(&RelativePath, &NixFile) -> validation::Result<ratchet::File>,
 ) -> validation::Result<BTreeMap<RelativePathBuf, ratchet::File>> {
     // Get all Nix files
     let files = {
         let mut files = vec![];
-        collect_nix_files(nixpkgs_path, &RelativePathBuf::new(), &mut files)?;
+        collect_nix_files(nixpkgs_path, &RelativePathBuf::new(), config, &mut files)?;
         files
     };
 
     let results = ResultIteratorExt::collect_vec(files.into_iter().map(|path| {
-        // Get the (optionally-cached) parsed Nix file
-        let nix_file = nix_file_store.get(&path.to_path(nixpkgs_path))?;
+        // Get the (optionally-cached) parsed Nix file, skipping files that can't be parsed, since
+        // `NixFileStore::validation` reports those
+        let Some(nix_file) = nix_file_store.get(&path.to_path(nixpkgs_path))? else {
+            return Ok(None);
+        };
         let result = f(&path, nix_file)?;
         let val = result.map(|ratchet| (path, ratchet));
-        Ok::<_, anyhow::Error>(val)
+        Ok::<_, anyhow::Error>(Some(val))
     }))?;
 
-    Ok(validation::sequence(results).map(|entries| {
-        // Convert the Vec to a BTreeMap
-        entries.into_iter().collect()
-    }))
+    Ok(
+        validation::sequence(results.into_iter().flatten()).map(|entries| {
+            // Convert the Vec to a BTreeMap
+            entries.into_iter().collect()
+        }),
+    )
 }
 
 /// Check that a Nix file is executable if and only if it has a shebang (`#!`) line.
@@ -91,19 +137,20 @@ fn check_invalid_escapes(
     let mut problems: Vec<Problem> = Vec::new();
 
     let mut report = |index: usize, prefix: &str, c: char, fixed: Option<String>| {
-        problems.push(
-            npv_170::NixFileContainsUselessEscape::new(
-                location::Location::new(
-                    relative_path,
-                    nix_file.line_index.line(index),
-                    nix_file.line_index.column(index),
-                ),
-                format!("{prefix}{c}"),
-                c.to_string(),
-                fixed,
-            )
-            .into(),
-        );
+        let problem: Problem = npv_170::NixFileContainsUselessEscape::new(
+            location::Location::new(
+                relative_path,
+                nix_file.line_index.line(index),
+                nix_file.line_index.column(index),
+            ),
+            format!("{prefix}{c}"),
+            c.to_string(),
+            fixed,
+        )
+        .into();
+        if !nix_file.suppressions.suppresses(problem.npv_code(), index) {
+            problems.push(problem);
+        }
     };
 
     for str_node in nix_file
@@ -151,18 +198,320 @@ fn check_invalid_escapes(
         }
     }
 
-    if problems.is_empty() {
-        Ok(Success(()))
+    Ok(validation::with_problems(problems))
+}
+
+/// Fetchers that support SRI hashes in their `hash` attribute.
+const FETCHERS: &[&str] = &[
+    "fetchurl",
+    "fetchzip",
+    "fetchgit",
+    "fetchsvn",
+    "fetchhg",
+    "fetchpatch",
+    "fetchpatch2",
+    "fetchFromGitHub",
+    "fetchFromGitLab",
+    "fetchFromGitea",
+    "fetchFromCodeberg",
+    "fetchFromBitbucket",
+    "fetchFromSourcehut",
+    "fetchFromSavannah",
+    "fetchFromRepoOrCz",
+    "fetchCrate",
+    "fetchPypi",
+    "fetchDebianPatch",
+];
+
+/// Hash attributes that fetchers support in addition to `hash`.
+const DEPRECATED_HASH_ATTRIBUTES: &[&str] = &["sha256", "sha512", "md5"];
+
+/// The placeholder hashes defined in `lib`.
+const PLACEHOLDER_HASHES: &[&str] = &["fakeHash", "fakeSha256", "fakeSha512"];
+
+/// Finds all hashes passed to fetchers in a Nix file that aren't real SRI hashes, for the
+/// ratchet check.
+fn check_fetcher_hashes(nix_file: &NixFile) -> ratchet::RatchetState<ratchet::FetcherHashes> {
+    let mut hashes = vec![];
+    for apply in nix_file
+        .syntax_root
+        .syntax()
+        .descendants()
+        .filter_map(ast::Apply::cast)
+    {
+        let Some(fetcher) = apply
+            .lambda()
+            .and_then(|lambda| function_name(&lambda, FETCHERS))
+        else {
+            continue;
+        };
+        let Some(ast::Expr::AttrSet(attr_set)) = apply.argument() else {
+            continue;
+        };
+        for attrpath_value in attr_set.attrpath_values() {
+            let Some(mut attrs) = attrpath_value.attrpath().map(|attrpath| attrpath.attrs()) else {
+                continue;
+            };
+            let (Some(ast::Attr::Ident(ident)), None) = (attrs.next(), attrs.next()) else {
+                continue;
+            };
+            let name = ident.to_string();
+            let is_deprecated = DEPRECATED_HASH_ATTRIBUTES.contains(&name.as_str());
+            if name != "hash" && !is_deprecated {
+                continue;
+            }
+
+            let kind = match attrpath_value.value().as_ref().and_then(placeholder_hash) {
+                Some(placeholder) => FetcherHashKind::Placeholder(placeholder),
+                None if is_deprecated => FetcherHashKind::DeprecatedAttribute(name),
+                None => continue,
+            };
+            hashes.push(FetcherHash {
+                line: nix_file
+                    .line_index
+                    .line(attrpath_value.syntax().text_range().start().into()),
+                fetcher: fetcher.clone(),
+                kind,
+            });
+        }
+    }
+
+    if hashes.is_empty() {
+        ratchet::RatchetState::Tight
+    } else {
+        ratchet::RatchetState::Loose(hashes)
+    }
+}
+
+/// Returns the name of the function in an expression like `fetchurl` or `pkgs.fetchurl`, if it's
+/// one of the `known` functions.
+fn function_name(function: &ast::Expr, known: &[&str]) -> Option<String> {
+    let name = match function {
+        ast::Expr::Ident(ident) => ident.to_string(),
+        ast::Expr::Select(select) => select.attrpath()?.attrs().last()?.to_string(),
+        _ => return None,
+    };
+    known.contains(&name.as_str()).then_some(name)
+}
+
+/// Returns the placeholder hash of an expression like `lib.fakeHash`, `""` or a hash only
+/// consisting of zeroes, if it is one.
+fn placeholder_hash(value: &ast::Expr) -> Option<String> {
+    match value {
+        ast::Expr::Ident(ident) if PLACEHOLDER_HASHES.contains(&ident.to_string().as_str()) => {
+            Some(ident.to_string())
+        }
+        ast::Expr::Select(select)
+            if select
+                .attrpath()?
+                .attrs()
+                .last()
+                .is_some_and(|attr| PLACEHOLDER_HASHES.contains(&attr.to_string().as_str())) =>
+        {
+            Some(select.to_string())
+        }
+        ast::Expr::Str(_) => {
+            let hash = nix_file::string_literal(value)?;
+            // The hash without its algorithm, if any, like `sha256-` or `sha256:`
+            let digest = ["sha1", "sha256", "sha512", "md5"]
+                .iter()
+                .find_map(|algorithm| hash.strip_prefix(algorithm)?.strip_prefix(['-', ':']))
+                .unwrap_or(&hash)
+                .trim_end_matches('=');
+            // Zeroes are encoded as `0` in base16 and Nix's base32, and as `A` in base64
+            (digest.chars().all(|c| c == '0') || digest.chars().all(|c| c == 'A'))
+                .then(|| format!("\"{hash}\""))
+        }
+        _ => None,
+    }
+}
+
+/// Functions creating derivations that support the `finalAttrs:` pattern.
+const DERIVATION_BUILDERS: &[&str] = &[
+    "mkDerivation",
+    "buildPythonPackage",
+    "buildPythonApplication",
+    "buildGoModule",
+    "buildRustPackage",
+    "buildNpmPackage",
+];
+
+/// Finds all calls in a Nix file creating derivations from a `rec` attribute set, for the ratchet
+/// check.
+fn check_rec_derivations(nix_file: &NixFile) -> ratchet::RatchetState<ratchet::FinalAttrs> {
+    let calls: Vec<RecDerivation> = nix_file
+        .syntax_root
+        .syntax()
+        .descendants()
+        .filter_map(ast::Apply::cast)
+        .filter_map(|apply| {
+            let lambda = apply.lambda()?;
+            function_name(&lambda, DERIVATION_BUILDERS)?;
+            let ast::Expr::AttrSet(attr_set) = apply.argument()? else {
+                return None;
+            };
+            attr_set.rec_token()?;
+            Some(RecDerivation {
+                line: nix_file
+                    .line_index
+                    .line(apply.syntax().text_range().start().into()),
+                function: lambda.to_string(),
+            })
+        })
+        .collect();
+
+    if calls.is_empty() {
+        ratchet::RatchetState::Tight
     } else {
-        Ok(Failure(problems))
+        ratchet::RatchetState::Loose(calls)
     }
 }
 
+/// The table of deprecated attributes, see `deprecated_attributes.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeprecatedAttributes {
+    attribute: Vec<DeprecatedAttribute>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeprecatedAttribute {
+    path: String,
+    advice: String,
+}
+
+static DEPRECATED_ATTRIBUTES: LazyLock<Vec<(Vec<String>, DeprecatedAttribute)>> =
+    LazyLock::new(|| {
+        let table: DeprecatedAttributes =
+            toml::from_str(include_str!("deprecated_attributes.toml"))
+                .expect("the table of deprecated attributes is valid");
+        table
+            .attribute
+            .into_iter()
+            .map(|attribute| {
+                let path = attribute.path.split('.').map(str::to_owned).collect();
+                (path, attribute)
+            })
+            .collect()
+    });
+
+/// Finds all uses of deprecated attributes in a Nix file, for the ratchet check.
+fn check_deprecated_attributes(
+    nix_file: &NixFile,
+) -> ratchet::RatchetState<ratchet::DeprecatedAttributes> {
+    let uses: Vec<DeprecatedAttributeUse> = nix_file
+        .syntax_root
+        .syntax()
+        .descendants()
+        .filter(|node| match node.kind() {
+            SyntaxKind::NODE_SELECT => true,
+            // Identifiers that are expressions on their own, not attribute names, bound names or
+            // the start of a selection
+            SyntaxKind::NODE_IDENT => node.parent().is_none_or(|parent| {
+                !matches!(
+                    parent.kind(),
+                    SyntaxKind::NODE_SELECT
+                        | SyntaxKind::NODE_ATTRPATH
+                        | SyntaxKind::NODE_INHERIT
+                        | SyntaxKind::NODE_PAT_ENTRY
+                        | SyntaxKind::NODE_PAT_BIND
+                        | SyntaxKind::NODE_IDENT_PARAM
+                )
+            }),
+            _ => false,
+        })
+        .filter_map(|node| {
+            let path = attribute_path(&ast::Expr::cast(node.clone())?)?;
+            let (_, deprecated) = DEPRECATED_ATTRIBUTES
+                .iter()
+                .find(|(deprecated, _)| path.windows(deprecated.len()).any(|w| w == deprecated))?;
+            Some(DeprecatedAttributeUse {
+                line: nix_file.line_index.line(node.text_range().start().into()),
+                expression: node.to_string(),
+                attribute: deprecated.path.clone(),
+                advice: deprecated.advice.clone(),
+            })
+        })
+        .collect();
+
+    if uses.is_empty() {
+        ratchet::RatchetState::Tight
+    } else {
+        ratchet::RatchetState::Loose(uses)
+    }
+}
+
+/// Returns the attribute path of an expression like `foo` or `foo.bar.baz`, if it only consists of
+/// identifiers.
+fn attribute_path(expr: &ast::Expr) -> Option<Vec<String>> {
+    match expr {
+        ast::Expr::Ident(ident) => Some(vec![ident.to_string()]),
+        ast::Expr::Select(select) => {
+            let mut path = attribute_path(&select.expr()?)?;
+            for attr in select.attrpath()?.attrs() {
+                let ast::Attr::Ident(ident) = attr else {
+                    return None;
+                };
+                path.push(ident.to_string());
+            }
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+/// Check that all suppression comments in a Nix file are valid and suppress at least one problem.
+fn check_suppressions(
+    relative_path: &RelativePath,
+    nix_file: &NixFile,
+    config: &Config,
+) -> validation::Validation<()> {
+    let location = |index: usize| {
+        location::Location::new(
+            relative_path,
+            nix_file.line_index.line(index),
+            nix_file.line_index.column(index),
+        )
+    };
+
+    let invalid = nix_file.suppressions.invalid.iter().map(|suppression| {
+        npv_171::NixFileContainsInvalidSuppression::new(
+            location(suppression.offset),
+            &suppression.text,
+            &suppression.reason,
+        )
+        .into()
+    });
+
+    // Suppressions for checks that didn't run can't have been used
+    let checks_ran = |codes: &[String]| {
+        codes
+            .iter()
+            .all(|code| problem::check(code).is_none_or(|check| config.runs(check.category)))
+    };
+
+    let unused = nix_file
+        .suppressions
+        .unused()
+        .filter(|suppression| checks_ran(&suppression.codes))
+        .map(|suppression| {
+            npv_172::NixFileContainsUnusedSuppression::new(
+                location(suppression.offset),
+                suppression.codes.clone(),
+            )
+            .into()
+        });
+
+    sequence_(invalid.chain(unused))
+}
+
 /// Recursively collects all Nix files in the relative `dir` within `base`
-/// into the `files` `Vec`.
+/// into the `files` `Vec`, skipping the paths ignored by the config.
 fn collect_nix_files(
     base: &Path,
     dir: &RelativePath,
+    config: &Config,
     files: &mut Vec<RelativePathBuf>,
 ) -> anyhow::Result<()> {
     for entry in structure::read_dir_sorted(&dir.to_path(base))? {
@@ -172,14 +521,184 @@ fn collect_nix_files(
         let absolute_path = entry.path();
 
         // We'll get to every file based on directory recursion, no need to follow symlinks.
-        if absolute_path.is_symlink() {
+        if absolute_path.is_symlink() || config.is_ignored(&relative_path) {
             continue;
         }
         if absolute_path.is_dir() {
-            collect_nix_files(base, &relative_path, files)?
+            collect_nix_files(base, &relative_path, config, files)?
         } else if absolute_path.extension().is_some_and(|x| x == "nix") {
             files.push(relative_path)
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detects_fetcher_hashes() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let file = temp_dir.path().join("file.nix");
+        let contents = indoc! {r#"
+            { lib, pkgs, fetchurl, fetchFromGitHub, fetchzip, fetchgit, mkDerivation }:
+            [
+              (fetchurl {
+                url = "https://example.org/foo.tar.gz";
+                sha256 = "1hv6zp2pq6qgs0n7bpjm6f5djf4ihrvqbsbrq4b3a0kg3fcbw8zy";
+              })
+              (fetchFromGitHub {
+                owner = "foo";
+                repo = "bar";
+                hash = lib.fakeHash;
+              })
+              (pkgs.fetchzip {
+                url = "https://example.org/foo.zip";
+                sha512 = "";
+              })
+              (fetchgit {
+                url = "https://example.org/foo.git";
+                hash = "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+              })
+              (fetchurl {
+                url = "https://example.org/bar.tar.gz";
+                hash = "sha256-2gP0vWEHEnLf3+uTsKwWOn71d5Hf0uWp1mj6cVaNgAg=";
+              })
+              (mkDerivation {
+                sha256 = "0000000000000000000000000000000000000000000000000000";
+              })
+            ]
+        "#};
+        fs::write(&file, contents)?;
+
+        let mut store = NixFileStore::new(temp_dir.path());
+        let nix_file = store.get(&file)?.expect("valid Nix file");
+        let ratchet::RatchetState::Loose(hashes) = check_fetcher_hashes(nix_file) else {
+            panic!("Expected fetcher hashes to be found");
+        };
+
+        let hash = |line, fetcher: &str, kind| FetcherHash {
+            line,
+            fetcher: fetcher.to_owned(),
+            kind,
+        };
+        assert_eq!(
+            hashes,
+            vec![
+                hash(
+                    5,
+                    "fetchurl",
+                    FetcherHashKind::DeprecatedAttribute("sha256".to_owned())
+                ),
+                hash(
+                    10,
+                    "fetchFromGitHub",
+                    FetcherHashKind::Placeholder("lib.fakeHash".to_owned())
+                ),
+                hash(
+                    14,
+                    "fetchzip",
+                    FetcherHashKind::Placeholder("\"\"".to_owned())
+                ),
+                hash(
+                    18,
+                    "fetchgit",
+                    FetcherHashKind::Placeholder(
+                        "\"sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\"".to_owned()
+                    )
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn detects_rec_derivations() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let file = temp_dir.path().join("file.nix");
+        let contents = indoc! {r#"
+            { stdenv, python3Packages, buildGoModule, lib }:
+            {
+              a = stdenv.mkDerivation rec {
+                pname = "a";
+                version = "1.0";
+              };
+              b = python3Packages.buildPythonPackage rec { pname = "b"; };
+              c = stdenv.mkDerivation (finalAttrs: { pname = "c"; });
+              d = buildGoModule { pname = "d"; };
+              e = lib.makeOverridable rec { pname = "e"; };
+            }
+        "#};
+        fs::write(&file, contents)?;
+
+        let mut store = NixFileStore::new(temp_dir.path());
+        let nix_file = store.get(&file)?.expect("valid Nix file");
+        let ratchet::RatchetState::Loose(calls) = check_rec_derivations(nix_file) else {
+            panic!("Expected rec derivations to be found");
+        };
+
+        assert_eq!(
+            calls,
+            vec![
+                RecDerivation {
+                    line: 3,
+                    function: "stdenv.mkDerivation".to_owned(),
+                },
+                RecDerivation {
+                    line: 7,
+                    function: "python3Packages.buildPythonPackage".to_owned(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn detects_deprecated_attributes() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let file = temp_dir.path().join("file.nix");
+        let contents = indoc! {r#"
+            { lib, pkgs, stdenv, substituteAll }:
+            let
+              inherit (lib) licenses;
+              isLinux = true;
+            in
+            {
+              a = pkgs.stdenv.lib.optional isLinux "a";
+              b = substituteAll { src = ./b; };
+              meta.license = [ licenses.gpl3 lib.licenses.gpl3Plus ];
+              meta.broken = stdenv.hostPlatform.isLinux || stdenv.isDarwin;
+              stdenv.lib = null;
+            }
+        "#};
+        fs::write(&file, contents)?;
+
+        let mut store = NixFileStore::new(temp_dir.path());
+        let nix_file = store.get(&file)?.expect("valid Nix file");
+        let ratchet::RatchetState::Loose(uses) = check_deprecated_attributes(nix_file) else {
+            panic!("Expected deprecated attributes to be found");
+        };
+
+        let expressions = uses
+            .iter()
+            .map(|deprecated| (deprecated.line, deprecated.expression.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expressions,
+            vec![
+                (7, "pkgs.stdenv.lib.optional"),
+                (8, "substituteAll"),
+                (9, "licenses.gpl3"),
+                (10, "stdenv.isDarwin"),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -9,29 +9,38 @@
 // #![allow(clippy::use_self)]
 // #![allow(clippy::missing_const_for_fn)]
 
+mod config;
 mod eval;
 mod files;
 mod location;
+mod migrate;
 mod nix_file;
+mod patch;
 mod problem;
 mod ratchet;
 mod references;
+mod stats;
 mod status;
 mod structure;
+mod suppression;
 mod validation;
 
 use anyhow::Context as _;
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use relative_path::RelativePath;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 use std::{panic, thread};
 
+use crate::config::{Config, Selection, Selector};
 use crate::nix_file::NixFileStore;
+use crate::problem::Category;
+use crate::stats::{Breakdown, Stats};
 use crate::status::{ColoredStatus, Status};
 use crate::structure::check_structure;
-use crate::validation::Validation::Failure;
-use crate::validation::Validation::Success;
+use crate::validation::ResultIteratorExt as _;
+use crate::validation::Validation::{self, Success};
 
 /// Program to check the validity of pkgs/by-name
 ///
@@ -47,20 +56,304 @@ use crate::validation::Validation::Success;
 /// - Informative messages
 /// - Detected problems if validation is not successful
 #[derive(Parser, Debug)]
-#[command(about, version, verbatim_doc_comment)]
+#[command(
+    about,
+    version,
+    verbatim_doc_comment,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the main Nixpkgs to check. For PRs, set this to a checkout of the PR branch.
-    nixpkgs: PathBuf,
+    #[arg(required = true)]
+    nixpkgs: Option<PathBuf>,
 
     /// Path to the base Nixpkgs to run ratchet checks against.
     /// For PRs, set this to a checkout of the PRs base branch.
+    /// If omitted, only <NIXPKGS> is checked, without any ratchet checks.
     #[arg(long)]
-    base: PathBuf,
+    base: Option<PathBuf>,
+
+    /// Only run these checks. Either categories (structure, references, eval, ratchet, files) or
+    /// problem codes, see `nixpkgs-vet list-checks`. Can be comma-separated or repeated.
+    /// The checks other checks depend on still run, but only report the problems that prevent
+    /// those from running.
+    #[arg(long, value_delimiter = ',')]
+    only: Vec<Selector>,
+
+    /// Don't run these checks, in the same format as `--only`.
+    /// For example, `--skip eval` skips the evaluation with Nix, and with it the ratchet checks of
+    /// packages, while the ratchet checks of Nix files (NPV-173, NPV-174, NPV-175) still run.
+    #[arg(long, value_delimiter = ',')]
+    skip: Vec<Selector>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the documentation of a problem code, e.g. `nixpkgs-vet explain NPV-142`
+    ///
+    /// Exit code:
+    /// - `0`: If the code exists
+    /// - `2`: If there is no such code
+    #[command(verbatim_doc_comment)]
+    Explain {
+        /// The problem code, as shown in the output of a check.
+        code: String,
+    },
+
+    /// List all problem codes with their category, default severity, whether they're ratchet
+    /// checks and whether they can be fixed automatically
+    ListChecks {
+        /// The output format.
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+
+    /// Count the packages in each state of each ratchet check
+    ///
+    /// This allows tracking the progress of migrations like the one to `pkgs/by-name`.
+    ///
+    /// Exit code:
+    /// - `0`: If the counting is successful
+    /// - `1`: If there are problems preventing the evaluation
+    /// - `2`: If an unexpected I/O error occurs
+    #[command(verbatim_doc_comment)]
+    Stats {
+        /// Path to the Nixpkgs to count the packages of.
+        nixpkgs: PathBuf,
+
+        /// Additionally break down the counts by the file defining each package or by each of
+        /// its maintainers.
+        #[arg(long, value_enum)]
+        by: Option<Breakdown>,
+
+        /// The output format.
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+
+    /// Move a package defined with `callPackage` in `pkgs/top-level/all-packages.nix` into
+    /// `pkgs/by-name`
+    ///
+    /// This moves the package file or directory, renames its `default.nix` to `package.nix`,
+    /// rewrites relative paths pointing outside of it, and removes the `callPackage` definition if
+    /// it doesn't pass any arguments.
+    ///
+    /// Exit code:
+    /// - `0`: If the package was migrated
+    /// - `1`: If there are problems preventing the evaluation
+    /// - `2`: If the package can't be migrated or an unexpected I/O error occurs
+    #[command(verbatim_doc_comment)]
+    Migrate {
+        /// The name of the top-level attribute of the package.
+        attribute: String,
+
+        /// Path to the Nixpkgs to change.
+        #[arg(long, default_value = ".")]
+        nixpkgs: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum Format {
+    /// A table for humans.
+    #[default]
+    Text,
+    /// A JSON array with an object for each check, for tooling.
+    Json,
 }
 
 fn main() -> ExitCode {
     let args = Args::parse();
-    let status: ColoredStatus = process(args.base, &args.nixpkgs).into();
+    match args.command {
+        Some(Command::Explain { code }) => explain(&code),
+        Some(Command::ListChecks { format }) => {
+            list_checks(format);
+            ExitCode::SUCCESS
+        }
+        Some(Command::Stats {
+            nixpkgs,
+            by,
+            format,
+        }) => stats(&nixpkgs, by, format),
+        Some(Command::Migrate { attribute, nixpkgs }) => migrate(&nixpkgs, &attribute),
+        None => {
+            let Some(nixpkgs) = args.nixpkgs else {
+                unreachable!("clap requires the Nixpkgs path without a subcommand");
+            };
+            let selection = Selection {
+                only: args.only,
+                skip: args.skip,
+            };
+            let status: ColoredStatus = process(args.base, &nixpkgs, selection).into();
+            eprintln!("{status}");
+            status.into()
+        }
+    }
+}
+
+/// Prints the documentation of a problem code.
+fn explain(code: &str) -> ExitCode {
+    match problem::check(code) {
+        Some(check) => {
+            print!("{}", check.explanation);
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("Unknown problem code {code}, expected a code like NPV-100");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints all checks with their metadata.
+fn list_checks(format: Format) {
+    match format {
+        Format::Text => {
+            println!("CODE     CATEGORY    SEVERITY  RATCHET  AUTO-FIX  TITLE");
+            for check in problem::CHECKS {
+                println!(
+                    "{:<8} {:<11} {:<9} {:<8} {:<9} {}",
+                    check.code,
+                    check.category.to_string(),
+                    check.default_severity.to_string(),
+                    if check.ratchet { "yes" } else { "no" },
+                    if check.auto_fix { "yes" } else { "no" },
+                    check.title(),
+                );
+            }
+        }
+        Format::Json => {
+            let checks: Vec<_> = problem::CHECKS
+                .iter()
+                .map(|check| {
+                    serde_json::json!({
+                        "code": check.code,
+                        "title": check.title(),
+                        "category": check.category.to_string(),
+                        "default_severity": check.default_severity.to_string(),
+                        "ratchet": check.ratchet,
+                        "auto_fix": check.auto_fix,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(checks));
+        }
+    }
+}
+
+/// Prints the ratchet statistics of a Nixpkgs.
+fn stats(nixpkgs_path: &Path, breakdown: Option<Breakdown>, format: Format) -> ExitCode {
+    let result = Config::load(nixpkgs_path).and_then(|config| {
+        // Only the checks the ratchets depend on need to run
+        let selection = Selection {
+            only: vec![Selector::Category(Category::Ratchet)],
+            skip: vec![],
+        };
+        check_nixpkgs(
+            nixpkgs_path,
+            &Config {
+                selection,
+                ..config
+            },
+        )
+    });
+
+    let status = match result.map(Validation::into_result) {
+        Ok(Ok((nixpkgs, _warnings))) => {
+            let stats = Stats::new(&nixpkgs, breakdown);
+            match format {
+                Format::Text => print!("{stats}"),
+                Format::Json => println!(
+                    "{}",
+                    serde_json::to_string(&stats).expect("stats can be serialised")
+                ),
+            }
+            return ExitCode::SUCCESS;
+        }
+        Ok(Err(problems)) => Status::ProblemsFound(problems),
+        Err(error) => error.into(),
+    };
+    let status: ColoredStatus = status.into();
+    eprintln!("{status}");
+    status.into()
+}
+
+/// Migrates a package to `pkgs/by-name`.
+fn migrate(nixpkgs_path: &Path, attribute: &str) -> ExitCode {
+    let result = nixpkgs_path
+        .canonicalize()
+        .with_context(|| {
+            format!(
+                "Nixpkgs path {} could not be resolved",
+                nixpkgs_path.display()
+            )
+        })
+        .and_then(|nixpkgs_path| {
+            let config = Config::load(&nixpkgs_path)?;
+            // Only the checks the ratchets depend on need to run
+            let selection = Selection {
+                only: vec![Selector::Category(Category::Ratchet)],
+                skip: vec![],
+            };
+            let result = check_nixpkgs(
+                &nixpkgs_path,
+                &Config {
+                    selection,
+                    ..config
+                },
+            )?;
+            let (mut nixpkgs, _warnings) = match result.into_result() {
+                Ok(result) => result,
+                Err(problems) => return Ok(Err(problems)),
+            };
+
+            let Some(package) = nixpkgs.packages.remove(attribute) else {
+                anyhow::bail!("There is no top-level attribute `{attribute}`");
+            };
+            let (call_package_info, definition_file) = match package.uses_by_name {
+                ratchet::RatchetState::Loose(context) => context,
+                ratchet::RatchetState::Tight => {
+                    anyhow::bail!("Attribute `{attribute}` already uses pkgs/by-name")
+                }
+                ratchet::RatchetState::NonApplicable => anyhow::bail!(
+                    "Attribute `{attribute}` can't be migrated, since it's not a derivation \
+                     defined with `callPackage` in the top-level package set"
+                ),
+            };
+            let Some(source) = call_package_info.relative_path else {
+                anyhow::bail!(
+                    "Attribute `{attribute}` can't be migrated, since the first argument of its \
+                     `callPackage` in {definition_file} is not a path"
+                );
+            };
+
+            let migration =
+                migrate::migrate_package(&nixpkgs_path, attribute, &source, &definition_file)?;
+            for change in migration.changes {
+                println!("{change}");
+            }
+            if !migration.escaping_paths.is_empty() {
+                eprintln!(
+                    "These path expressions point outside the package directory and need to be \
+                     changed manually, see `nixpkgs-vet explain NPV-123`:"
+                );
+                for path in migration.escaping_paths {
+                    eprintln!("- {path}");
+                }
+            }
+            Ok(Ok(()))
+        });
+
+    let status = match result {
+        Ok(Ok(())) => return ExitCode::SUCCESS,
+        Ok(Err(problems)) => Status::ProblemsFound(problems),
+        Err(error) => error.into(),
+    };
+    let status: ColoredStatus = status.into();
     eprintln!("{status}");
     status.into()
 }
@@ -68,12 +361,36 @@ fn main() -> ExitCode {
 /// Does the actual work. This is the abstraction used both by `main` and the tests.
 ///
 /// # Arguments
-/// - `base_nixpkgs`: Path to the base Nixpkgs to run ratchet checks against.
-/// - `main_nixpkgs`: Path to the main Nixpkgs to check.
-fn process(base_nixpkgs: PathBuf, main_nixpkgs: &Path) -> Status {
+/// - `base_nixpkgs`: Path to the base Nixpkgs to run ratchet checks against. Without it, only
+///   the main Nixpkgs is checked and the ratchet checks are skipped.
+/// - `main_nixpkgs`: Path to the main Nixpkgs to check. Its `nixpkgs-vet.toml` configures the
+///   checks for both.
+/// - `selection`: The checks selected on the command line.
+fn process(base_nixpkgs: Option<PathBuf>, main_nixpkgs: &Path, selection: Selection) -> Status {
+    let config = match Config::load(main_nixpkgs) {
+        Ok(config) => Config {
+            selection,
+            ..config
+        },
+        Err(error) => {
+            return error.into();
+        }
+    };
+
+    let Some(base_nixpkgs) = base_nixpkgs else {
+        // Without a base there's nothing to ratchet against, so only the main Nixpkgs matters.
+        return match check_nixpkgs(main_nixpkgs, &config).map(Validation::into_result) {
+            Ok(Err(problems)) => Status::ProblemsFound(problems),
+            Ok(Ok((_, warnings))) if warnings.is_empty() => Status::ValidatedSuccessfully,
+            Ok(Ok((_, warnings))) => Status::ValidatedWithWarnings(warnings),
+            Err(error) => error.into(),
+        };
+    };
+
     // Very easy to parallelise this, since both operations are totally independent of each other.
-    let base_thread = thread::spawn(move || check_nixpkgs(&base_nixpkgs));
-    let main_result = match check_nixpkgs(main_nixpkgs) {
+    let base_config = config.clone();
+    let base_thread = thread::spawn(move || check_nixpkgs(&base_nixpkgs, &base_config));
+    let main_result = match check_nixpkgs(main_nixpkgs, &config) {
         Ok(result) => result,
         Err(error) => {
             return error.into();
@@ -88,15 +405,31 @@ fn process(base_nixpkgs: PathBuf, main_nixpkgs: &Path) -> Status {
         Err(e) => panic::resume_unwind(e),
     };
 
-    match (base_result, main_result) {
-        (Failure(..), Failure(errors)) => Status::BranchStillBroken(errors),
-        (Success(..), Failure(errors)) => Status::ProblemsIntroduced(errors),
-        (Failure(..), Success(..)) => Status::BranchHealed,
-        (Success(base), Success(main)) => {
+    // Only errors fail the validation, but warnings of the main branch are reported regardless.
+    match (base_result.into_result(), main_result.into_result()) {
+        (Err(..), Err(problems)) => Status::BranchStillBroken(problems),
+        (Ok(..), Err(problems)) => Status::ProblemsIntroduced(problems),
+        (Err(..), Ok((_, warnings))) => Status::BranchHealed(warnings),
+        (Ok((base, _)), Ok((main, mut warnings))) => {
             // Both base and main branch succeed. Check ratchet state between them...
-            match ratchet::Nixpkgs::compare(&base, main) {
-                Failure(errors) => Status::DiscouragedPatternedIntroduced(errors),
-                Success(..) => Status::ValidatedSuccessfully,
+            let ratchet_result = if config.runs(Category::Ratchet) {
+                ratchet::Nixpkgs::compare(&base, main, &config.ratchets)
+            } else {
+                Success(())
+            };
+            match config.filter(ratchet_result).into_result() {
+                Err(problems) => {
+                    warnings.extend(problems);
+                    Status::DiscouragedPatternedIntroduced(warnings)
+                }
+                Ok(((), ratchet_warnings)) => {
+                    warnings.extend(ratchet_warnings);
+                    if warnings.is_empty() {
+                        Status::ValidatedSuccessfully
+                    } else {
+                        Status::ValidatedWithWarnings(warnings)
+                    }
+                }
             }
         }
     }
@@ -107,7 +440,7 @@ fn process(base_nixpkgs: PathBuf, main_nixpkgs: &Path) -> Status {
 /// This does not include ratchet checks, see ../README.md#ratchet-checks
 /// Instead a `ratchet::Nixpkgs` value is returned, whose `compare` method allows performing the
 /// ratchet check against another result.
-fn check_nixpkgs(nixpkgs_path: &Path) -> validation::Result<ratchet::Nixpkgs> {
+fn check_nixpkgs(nixpkgs_path: &Path, config: &Config) -> validation::Result<ratchet::Nixpkgs> {
     let nixpkgs_path = nixpkgs_path.canonicalize().with_context(|| {
         format!(
             "Nixpkgs path {} could not be resolved",
@@ -115,30 +448,69 @@ fn check_nixpkgs(nixpkgs_path: &Path) -> validation::Result<ratchet::Nixpkgs> {
         )
     })?;
 
-    let mut nix_file_store = NixFileStore::default();
+    let mut nix_file_store = NixFileStore::new(&nixpkgs_path);
 
     let package_result = {
-        if !nixpkgs_path.join(structure::BASE_SUBPATH).exists() {
-            // No pkgs/by-name directory, always valid
+        if !nixpkgs_path.join(structure::BASE_SUBPATH).exists() || !config.runs(Category::Structure)
+        {
+            // No pkgs/by-name directory or no checks needing it, always valid
             Success(BTreeMap::new())
         } else {
-            let structure = check_structure(&nixpkgs_path, &mut nix_file_store)?;
+            let structure = check_structure(
+                &nixpkgs_path,
+                RelativePath::new(structure::BASE_SUBPATH),
+                config,
+                &mut nix_file_store,
+            )?;
+            // Syntax errors in `pkgs/by-name` would make the evaluation fail
+            let structure = config.filter(nix_file_store.validation()).and_(structure);
 
             // Only if we could successfully parse the structure, we do the evaluation checks
             structure.result_map(|package_names| {
-                eval::check_values(&nixpkgs_path, &mut nix_file_store, package_names.as_slice())
+                if config.runs(Category::Evaluation) {
+                    eval::check_values(
+                        &nixpkgs_path,
+                        config,
+                        &mut nix_file_store,
+                        package_names.as_slice(),
+                    )
+                } else {
+                    Ok(Success(BTreeMap::new()))
+                }
             })?
         }
     };
 
-    let file_result = files::check_files(&nixpkgs_path, &mut nix_file_store)?;
+    // Configured `pkgs/by-name`-like directories only get structure and reference checks, since
+    // their packages aren't top-level attributes that could be evaluated.
+    let extra_by_name_result = validation::sequence_(
+        config
+            .extra_by_name_roots()
+            .filter(|_| config.runs(Category::Structure))
+            // Like with `pkgs/by-name`, a missing directory is always valid
+            .filter(|by_name_dir| by_name_dir.to_path(&nixpkgs_path).exists())
+            .map(|by_name_dir| {
+                let structure =
+                    check_structure(&nixpkgs_path, &by_name_dir, config, &mut nix_file_store)?;
+                Ok::<_, anyhow::Error>(structure.map(|_package_names| ()))
+            })
+            .collect_vec()?,
+    );
+    let package_result = package_result.and(extra_by_name_result, |packages, ()| packages);
+
+    let file_result = if config.runs(Category::Files) {
+        files::check_files(&nixpkgs_path, config, &mut nix_file_store)?
+    } else {
+        Success(BTreeMap::new())
+    };
 
-    Ok(
-        package_result.and(file_result, |packages, files| ratchet::Nixpkgs {
-            packages,
-            files,
-        }),
-    )
+    let result = package_result.and(file_result, |packages, files| ratchet::Nixpkgs {
+        packages,
+        files,
+    });
+    // The syntax errors of all other files that were parsed by the checks
+    let syntax_result = config.filter(nix_file_store.validation());
+    Ok(result.and(syntax_result, |nixpkgs, ()| nixpkgs))
 }
 
 #[cfg(test)]
@@ -150,7 +522,7 @@ mod tests {
     use pretty_assertions::StrComparison;
     use tempfile::{TempDir, tempdir_in};
 
-    use super::{process, structure::BASE_SUBPATH};
+    use super::{config::Selection, process, structure::BASE_SUBPATH};
 
     // Manually repeat this for each subdir under tests/ in order to disambiguate
     #[fixtures::fixtures(["tests/top-level/*"])]
@@ -165,7 +537,7 @@ mod tests {
         let expected_errors = fs::read_to_string(path.join("expected"))
             .with_context(|| format!("No expected file for test {name}"))?;
 
-        test_nixpkgs(&name, path, &expected_errors);
+        test_nixpkgs(&name, path, Selection::default(), &expected_errors);
         Ok(())
     }
 
@@ -199,7 +571,8 @@ mod tests {
         test_nixpkgs(
             "case_sensitive",
             path,
-            "- pkgs/by-name/fo: Duplicate case-sensitive package directories \"foO\" and \"foo\". (https://github.com/NixOS/nixpkgs-vet/wiki/NPV-111)\n\
+            Selection::default(),
+            "- pkgs/by-name/fo: Duplicate case-sensitive package directories \"foO\" and \"foo\". (see `nixpkgs-vet explain NPV-111` or https://github.com/NixOS/nixpkgs-vet/wiki/NPV-111)\n\
             This PR introduces the problems listed above. Please fix them before merging, \
             otherwise the base branch would break.\n",
         );
@@ -229,13 +602,91 @@ mod tests {
             test_nixpkgs(
                 "symlinked_tmpdir",
                 Path::new("tests/top-level/success"),
+                Selection::default(),
                 "Validated successfully\n",
             );
         });
         Ok(())
     }
 
-    fn test_nixpkgs(name: &str, path: &Path, expected_errors: &str) {
+    /// Skipping the evaluation means that Nix doesn't need to be available.
+    #[test]
+    fn test_skip_eval() {
+        test_nixpkgs(
+            "skip_eval",
+            Path::new("tests/top-level/success"),
+            Selection {
+                only: vec![],
+                skip: vec!["eval".parse().expect("valid selector")],
+            },
+            "Validated successfully\n",
+        );
+    }
+
+    /// The ratchet checks of Nix files don't need the evaluation, so they still run.
+    #[test]
+    fn test_skip_eval_file_ratchets() {
+        test_nixpkgs(
+            "skip_eval_file_ratchets",
+            Path::new("tests/top-level/fetcher-hashes"),
+            Selection {
+                only: vec![],
+                skip: vec!["eval".parse().expect("valid selector")],
+            },
+            &fs::read_to_string("tests/top-level/fetcher-hashes/expected").expect("expected file"),
+        );
+    }
+
+    /// Only the selected problem is reported, and the structure checks aren't run at all.
+    #[test]
+    fn test_only_code() {
+        test_nixpkgs(
+            "only_code",
+            Path::new("tests/top-level/severity"),
+            Selection {
+                only: vec!["NPV-170".parse().expect("valid selector")],
+                skip: vec![],
+            },
+            "- escape.nix: line 2, column 17 contains the escape \"\\.\".\n  \
+             This escape has no effect; it is equivalent to \".\".\n  \
+             Depending on your intention, either change it to that, or to the non-equivalent \"\\\\.\".\n \
+             (warning: see `nixpkgs-vet explain NPV-170` or https://github.com/NixOS/nixpkgs-vet/wiki/NPV-170)\n\
+             Validated successfully, but with the warnings listed above\n",
+        );
+    }
+
+    /// Problems found by the evaluation are filtered too.
+    #[test]
+    fn test_skip_code() {
+        test_nixpkgs(
+            "skip_code",
+            Path::new("tests/top-level/by-name-empty-override"),
+            Selection {
+                only: vec![],
+                skip: vec!["NPV-105".parse().expect("valid selector")],
+            },
+            "Validated successfully\n",
+        );
+    }
+
+    /// Without a base, the main Nixpkgs is checked on its own.
+    #[test]
+    fn test_no_base() {
+        let status = process(
+            None,
+            Path::new("tests/top-level/invalid-shard-name/main"),
+            Selection::default(),
+        );
+        assert_eq!(
+            status.to_string(),
+            "- pkgs/by-name/A: Invalid directory name \"A\", must be at most 2 ASCII characters, \
+             starting with a-z or \"_\", consisting of a-z, 0-9, \"-\" or \"_\". \
+             (see `nixpkgs-vet explain NPV-110` or https://github.com/NixOS/nixpkgs-vet/wiki/NPV-110)\n\
+             Found the problems listed above. Please fix them."
+        );
+    }
+
+    fn test_nixpkgs(name: &str, path: &Path, selection: Selection, expected_errors: &str) {
         // Match the expected errors almost verbatim -- `@REDACTED@` turns into `.*`.
         let pattern = format!(
             "^{}$",
@@ -261,7 +712,7 @@ mod tests {
         let nix_conf_dir = nix_conf_dir.path().as_os_str();
 
         let status = temp_env::with_var("NIX_CONF_DIR", Some(nix_conf_dir), || {
-            process(base_nixpkgs, &main_path)
+            process(Some(base_nixpkgs), &main_path, selection)
         });
 
         let actual_errors = format!("{status}\n");
@@ -0,0 +1,565 @@
+//! Moving packages defined with `callPackage` in `pkgs/top-level/all-packages.nix` into
+//! `pkgs/by-name`, see `nixpkgs-vet migrate`.
+//!
+//! This is the automatic fix for the `uses_by_name` ratchet. For a definition like
+//!
+//! ```nix
+//! foo = callPackage ../tools/foo { };
+//! ```
+//!
+//! the package directory (or file) is moved to `pkgs/by-name/fo/foo`, its `default.nix` is
+//! renamed to `package.nix`, and the definition is removed. If the definition passes arguments,
+//! it's kept with the path pointing to the new `package.nix` instead.
+//! Packages that other files in `pkgs` refer to with path expressions aren't migrated.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Context;
+use relative_path::{RelativePath, RelativePathBuf};
+use rnix::ast::{self, Expr, HasEntry};
+use rowan::TextRange;
+use rowan::ast::AstNode;
+
+use crate::nix_file::{NixFile, NixFileStore, ResolvedPath};
+use crate::structure::{self, PACKAGE_NIX_FILENAME};
+
+/// The result of a successful migration, for printing to the user.
+pub struct Migration {
+    /// What has been done, one line each.
+    pub changes: Vec<String>,
+    /// Path expressions that now point outside the package directory, which need to be fixed
+    /// manually to satisfy the references check.
+    pub escaping_paths: Vec<String>,
+}
+
+/// Moves the package file or directory `source` of `attribute`, which is defined by
+/// `callPackage` in `definition_file`, into `pkgs/by-name`. All paths are relative to
+/// `nixpkgs_path`, which needs to be canonical.
+pub fn migrate_package(
+    nixpkgs_path: &Path,
+    attribute: &str,
+    source: &RelativePath,
+    definition_file: &RelativePath,
+) -> anyhow::Result<Migration> {
+    let target_dir = structure::relative_dir_for_package(attribute).to_path(nixpkgs_path);
+    if target_dir.exists() {
+        anyhow::bail!(
+            "The package directory {} already exists",
+            structure::relative_dir_for_package(attribute)
+        );
+    }
+
+    // A mapping from the absolute paths of all files to move to their new absolute paths.
+    let absolute_source = source.to_path(nixpkgs_path);
+    let mut moves = BTreeMap::new();
+    if absolute_source.is_dir() {
+        if !absolute_source.join("default.nix").is_file() {
+            anyhow::bail!("The package directory {source} doesn't contain a default.nix file");
+        }
+        collect_files(&absolute_source, &target_dir, &mut moves)?;
+        moves.insert(
+            absolute_source.join("default.nix"),
+            target_dir.join(PACKAGE_NIX_FILENAME),
+        );
+    } else {
+        moves.insert(
+            absolute_source.clone(),
+            target_dir.join(PACKAGE_NIX_FILENAME),
+        );
+    }
+
+    // Figure out all the changes before touching any files, so that nothing is left half-done
+    // in case of errors.
+    let mut nix_file_store = NixFileStore::new(nixpkgs_path);
+    let mut escaping_paths = vec![];
+    let mut new_contents = BTreeMap::new();
+    for (old_path, new_path) in &moves {
+        if old_path
+            .extension()
+            .is_none_or(|extension| extension != "nix")
+        {
+            continue;
+        }
+        let nix_file = parsed(&mut nix_file_store, old_path)?;
+        let mut edits = vec![];
+        for path in nix_file
+            .syntax_root
+            .syntax()
+            .descendants()
+            .filter_map(ast::Path::cast)
+        {
+            let ResolvedPath::Within(target) = nix_file.static_resolve_path(&path, nixpkgs_path)
+            else {
+                // Paths that can't be resolved statically can't be rewritten either
+                continue;
+            };
+            let new_target = new_location(
+                &moves,
+                &absolute_source,
+                &target_dir,
+                target.to_path(nixpkgs_path),
+            );
+            let new_parent = new_path.parent().expect("moved files have a parent");
+            let text = relative_nix_path(new_parent, &new_target);
+            if text != path.to_string() {
+                edits.push((path.syntax().text_range(), text.clone()));
+            }
+            if !new_target.starts_with(&target_dir) {
+                let relative_new_path = relative_to(nixpkgs_path, new_path);
+                escaping_paths.push(format!("{relative_new_path}: {text}"));
+            }
+        }
+        if !edits.is_empty() {
+            let contents = fs::read_to_string(old_path)
+                .with_context(|| format!("Could not read file {}", old_path.display()))?;
+            new_contents.insert(old_path.clone(), apply_edits(&contents, edits));
+        }
+    }
+
+    let absolute_definition_file = definition_file.to_path(nixpkgs_path);
+    let (definition_contents, definition_removed, definition_path) = {
+        let nix_file = parsed(&mut nix_file_store, &absolute_definition_file)?;
+        let Some((definition, path, arguments)) = nix_file
+            .syntax_root
+            .syntax()
+            .descendants()
+            .filter_map(ast::AttrpathValue::cast)
+            .find_map(|definition| {
+                let (path, arguments) = call_package_of(&definition, attribute)?;
+                match nix_file.static_resolve_path(&path, nixpkgs_path) {
+                    ResolvedPath::Within(target) if target == source => {
+                        Some((definition, path, arguments))
+                    }
+                    _ => None,
+                }
+            })
+        else {
+            anyhow::bail!(
+                "Could not find the definition `{attribute} = callPackage <path> ...` in \
+                 {definition_file} with <path> pointing to {source}"
+            );
+        };
+
+        let contents = fs::read_to_string(&absolute_definition_file).with_context(|| {
+            format!("Could not read file {}", absolute_definition_file.display())
+        })?;
+
+        // Without any arguments, the automatic definition from `pkgs/by-name` is equivalent.
+        let is_empty = matches!(
+            arguments,
+            Expr::AttrSet(ref attr_set) if attr_set.entries().next().is_none()
+        );
+        if is_empty {
+            let range = line_range(&contents, definition.syntax().text_range());
+            let contents = apply_edits(&contents, vec![(range, String::new())]);
+            (contents, true, path.syntax().text_range())
+        } else {
+            let new_path = relative_nix_path(
+                absolute_definition_file
+                    .parent()
+                    .expect("files have a parent"),
+                &target_dir.join(PACKAGE_NIX_FILENAME),
+            );
+            let range = path.syntax().text_range();
+            (
+                apply_edits(&contents, vec![(range, new_path)]),
+                false,
+                range,
+            )
+        }
+    };
+
+    // Paths from other files into the package would break, and they can't be rewritten in
+    // general, since they might be `callPackage` calls that need to use the new attribute instead.
+    let mut references = vec![];
+    collect_references(
+        nixpkgs_path,
+        &nixpkgs_path.join("pkgs"),
+        source,
+        &moves,
+        &mut nix_file_store,
+        &mut references,
+    )?;
+    references
+        .retain(|(file, range, _)| *file != absolute_definition_file || *range != definition_path);
+    if !references.is_empty() {
+        let references: Vec<_> = references
+            .iter()
+            .map(|(file, _, text)| format!("\n- {}: {text}", relative_to(nixpkgs_path, file)))
+            .collect();
+        anyhow::bail!(
+            "These path expressions point into {source} and need to be changed manually before \
+             it can be migrated:{}",
+            references.concat()
+        );
+    }
+
+    // Now actually perform the changes
+    let mut changes = vec![];
+    for (old_path, new_path) in &moves {
+        fs::create_dir_all(new_path.parent().expect("moved files have a parent"))?;
+        match new_contents.get(old_path) {
+            Some(contents) => {
+                fs::write(new_path, contents)?;
+                fs::remove_file(old_path)?;
+            }
+            None => fs::rename(old_path, new_path)?,
+        }
+        changes.push(format!(
+            "Moved {} to {}",
+            relative_to(nixpkgs_path, old_path),
+            relative_to(nixpkgs_path, new_path)
+        ));
+    }
+    if absolute_source.is_dir() {
+        remove_empty_dirs(&absolute_source)?;
+    }
+
+    fs::write(&absolute_definition_file, definition_contents)?;
+    changes.push(if definition_removed {
+        format!("Removed the definition of `{attribute}` from {definition_file}")
+    } else {
+        format!(
+            "Changed the definition of `{attribute}` in {definition_file} to use {}, since it \
+             passes arguments",
+            structure::relative_file_for_package(attribute)
+        )
+    });
+
+    Ok(Migration {
+        changes,
+        escaping_paths,
+    })
+}
+
+/// Returns the path and the arguments of a definition `<attribute> = callPackage <path> <args>`.
+fn call_package_of(definition: &ast::AttrpathValue, attribute: &str) -> Option<(ast::Path, Expr)> {
+    let attrs: Vec<_> = definition.attrpath()?.attrs().collect();
+    let [ast::Attr::Ident(ident)] = attrs.as_slice() else {
+        return None;
+    };
+    if ident.ident_token()?.text() != attribute {
+        return None;
+    }
+
+    let Expr::Apply(apply1) = definition.value()? else {
+        return None;
+    };
+    let Expr::Apply(apply2) = apply1.lambda()? else {
+        return None;
+    };
+    let function = match apply2.lambda()? {
+        Expr::Ident(ident) => ident,
+        Expr::Select(select) => match select.attrpath()?.attrs().last()? {
+            ast::Attr::Ident(ident) => ident,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if function.ident_token()?.text() != "callPackage" {
+        return None;
+    }
+    let Expr::PathRel(path) = apply2.argument()? else {
+        return None;
+    };
+    Some((ast::Path::PathRel(path), apply1.argument()?))
+}
+
+/// The location of `path` after the migration. Besides the moved files, this also maps
+/// directories within the package directory `source`, like `./.` or `./sources`.
+fn new_location(
+    moves: &BTreeMap<PathBuf, PathBuf>,
+    source: &Path,
+    target_dir: &Path,
+    path: PathBuf,
+) -> PathBuf {
+    if let Some(new_path) = moves.get(&path) {
+        return new_path.clone();
+    }
+    match path.strip_prefix(source) {
+        Ok(subpath) if source.is_dir() => target_dir.join(subpath),
+        _ => path,
+    }
+}
+
+/// Parses a Nix file that needs to be rewritten, failing if it has syntax errors.
+fn parsed<'a>(nix_file_store: &'a mut NixFileStore, path: &Path) -> anyhow::Result<&'a NixFile> {
+    nix_file_store
+        .get(path)?
+        .with_context(|| format!("Could not parse file {} with rnix", path.display()))
+}
+
+/// Collects the path expressions in all Nix files within `dir` that point to `source` or into it,
+/// skipping the files in `moves`. Files with syntax errors are skipped too.
+fn collect_references(
+    nixpkgs_path: &Path,
+    dir: &Path,
+    source: &RelativePath,
+    moves: &BTreeMap<PathBuf, PathBuf>,
+    nix_file_store: &mut NixFileStore,
+    references: &mut Vec<(PathBuf, TextRange, String)>,
+) -> anyhow::Result<()> {
+    for entry in structure::read_dir_sorted(dir)? {
+        let path = entry.path();
+        if path.is_symlink() || moves.contains_key(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            collect_references(
+                nixpkgs_path,
+                &path,
+                source,
+                moves,
+                nix_file_store,
+                references,
+            )?;
+            continue;
+        }
+        if path.extension().is_none_or(|extension| extension != "nix") {
+            continue;
+        }
+        let Some(nix_file) = nix_file_store.get(&path)? else {
+            continue;
+        };
+        for node in nix_file
+            .syntax_root
+            .syntax()
+            .descendants()
+            .filter_map(ast::Path::cast)
+        {
+            if let ResolvedPath::Within(target) = nix_file.static_resolve_path(&node, nixpkgs_path)
+                && target.starts_with(source)
+            {
+                references.push((path.clone(), node.syntax().text_range(), node.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collects all files in `dir` into `moves`, mapping them to the same subpath in `target_dir`.
+fn collect_files(
+    dir: &Path,
+    target_dir: &Path,
+    moves: &mut BTreeMap<PathBuf, PathBuf>,
+) -> anyhow::Result<()> {
+    for entry in structure::read_dir_sorted(dir)? {
+        let path = entry.path();
+        let target = target_dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, &target, moves)?;
+        } else {
+            moves.insert(path, target);
+        }
+    }
+    Ok(())
+}
+
+/// Removes a directory tree that only contains (empty) directories anymore.
+fn remove_empty_dirs(dir: &Path) -> anyhow::Result<()> {
+    for entry in structure::read_dir_sorted(dir)? {
+        remove_empty_dirs(&entry.path())?;
+    }
+    fs::remove_dir(dir).with_context(|| format!("Could not remove directory {}", dir.display()))
+}
+
+/// The relative Nix path expression pointing from `from_dir` to `to`, both absolute.
+fn relative_nix_path(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut segments: Vec<String> = vec!["..".to_owned(); from.len() - common];
+    if segments.is_empty() {
+        segments.push(".".to_owned());
+    }
+    let rest: Vec<String> = to[common..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if rest.is_empty() {
+        // Path expressions can't end with `..`
+        segments.push(".".to_owned());
+    }
+    segments.extend(rest);
+    segments.join("/")
+}
+
+/// A path relative to the Nixpkgs root, for messages.
+fn relative_to(nixpkgs_path: &Path, path: &Path) -> RelativePathBuf {
+    RelativePathBuf::from_path(path.strip_prefix(nixpkgs_path).unwrap_or(path))
+        .expect("a relative path")
+}
+
+/// Extends a range to the whole lines it's on, if there's nothing else on those lines.
+fn line_range(contents: &str, range: TextRange) -> TextRange {
+    let start = usize::from(range.start());
+    let end = usize::from(range.end());
+
+    // Include the `;` of the definition and the rest of the line
+    let line_end = contents[end..]
+        .find('\n')
+        .map_or(contents.len(), |i| end + i + 1);
+    let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+    if contents[line_start..start].trim().is_empty()
+        && contents[end..line_end]
+            .trim()
+            .trim_start_matches(';')
+            .is_empty()
+    {
+        TextRange::new(
+            u32::try_from(line_start).expect("small file").into(),
+            u32::try_from(line_end).expect("small file").into(),
+        )
+    } else {
+        range
+    }
+}
+
+/// Replaces the given non-overlapping ranges of a string.
+fn apply_edits(contents: &str, mut edits: Vec<(TextRange, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start());
+    let mut result = String::with_capacity(contents.len());
+    let mut position = 0;
+    for (range, replacement) in edits {
+        result.push_str(&contents[position..usize::from(range.start())]);
+        result.push_str(&replacement);
+        position = range.end().into();
+    }
+    result.push_str(&contents[position..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn relative_nix_paths() {
+        let path = |from: &str, to: &str| relative_nix_path(Path::new(from), Path::new(to));
+        assert_eq!(path("/a/b", "/a/b/c.nix"), "./c.nix");
+        assert_eq!(path("/a/b", "/a/c/d.nix"), "../c/d.nix");
+        assert_eq!(path("/a/b/c", "/a"), "../../.");
+        assert_eq!(path("/a", "/a"), "./.");
+    }
+
+    #[test]
+    fn migrates_package() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+        let write = |path: &str, contents: &str| -> anyhow::Result<()> {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("parent"))?;
+            Ok(fs::write(path, contents)?)
+        };
+
+        write(
+            "pkgs/top-level/all-packages.nix",
+            indoc! {"
+                self: with self; {
+                  foo = callPackage ../tools/foo { };
+                  bar = callPackage ../tools/bar.nix { withBaz = true; };
+                }
+            "},
+        )?;
+        write(
+            "pkgs/tools/foo/default.nix",
+            "{ }: [ ./fix.patch ./nested/script.sh ../common.nix ./. (import ./sources) ]\n",
+        )?;
+        write("pkgs/tools/foo/fix.patch", "")?;
+        write("pkgs/tools/foo/sources/default.nix", "[ ../fix.patch ]\n")?;
+        write("pkgs/tools/foo/nested/script.sh", "")?;
+        write("pkgs/tools/common.nix", "{ }\n")?;
+        write("pkgs/tools/bar.nix", "{ }: null\n")?;
+
+        let migration = migrate_package(
+            &root,
+            "foo",
+            RelativePath::new("pkgs/tools/foo"),
+            RelativePath::new("pkgs/top-level/all-packages.nix"),
+        )?;
+        assert_eq!(
+            migration.escaping_paths,
+            ["pkgs/by-name/fo/foo/package.nix: ../../../tools/common.nix"]
+        );
+        assert!(!root.join("pkgs/tools/foo").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("pkgs/by-name/fo/foo/package.nix"))?,
+            "{ }: [ ./fix.patch ./nested/script.sh ../../../tools/common.nix ./. (import ./sources) ]\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("pkgs/by-name/fo/foo/sources/default.nix"))?,
+            "[ ../fix.patch ]\n"
+        );
+        assert!(root.join("pkgs/by-name/fo/foo/nested/script.sh").exists());
+
+        // Definitions with arguments are kept, but point to the new location
+        migrate_package(
+            &root,
+            "bar",
+            RelativePath::new("pkgs/tools/bar.nix"),
+            RelativePath::new("pkgs/top-level/all-packages.nix"),
+        )?;
+        assert_eq!(
+            fs::read_to_string(root.join("pkgs/top-level/all-packages.nix"))?,
+            indoc! {"
+                self: with self; {
+                  bar = callPackage ../by-name/ba/bar/package.nix { withBaz = true; };
+                }
+            "}
+        );
+        assert!(root.join("pkgs/by-name/ba/bar/package.nix").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_referenced_package() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+        let write = |path: &str, contents: &str| -> anyhow::Result<()> {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("parent"))?;
+            Ok(fs::write(path, contents)?)
+        };
+
+        write(
+            "pkgs/top-level/all-packages.nix",
+            indoc! {"
+                self: with self; {
+                  foo = callPackage ../tools/foo { };
+                  fooWithBar = callPackage ../tools/foo { withBar = true; };
+                }
+            "},
+        )?;
+        write("pkgs/tools/foo/default.nix", "{ }: ./fix.patch\n")?;
+        write("pkgs/tools/foo/fix.patch", "")?;
+        write("pkgs/tools/baz/default.nix", "{ }: ../foo/fix.patch\n")?;
+
+        let error = migrate_package(
+            &root,
+            "foo",
+            RelativePath::new("pkgs/tools/foo"),
+            RelativePath::new("pkgs/top-level/all-packages.nix"),
+        )
+        .err()
+        .expect("migration fails");
+        assert_eq!(
+            error.to_string().lines().skip(1).collect::<Vec<_>>(),
+            [
+                "- pkgs/tools/baz/default.nix: ../foo/fix.patch",
+                "- pkgs/top-level/all-packages.nix: ../tools/foo",
+            ]
+        );
+        // Nothing is changed
+        assert!(root.join("pkgs/tools/foo/default.nix").exists());
+        assert!(!root.join("pkgs/by-name").exists());
+
+        Ok(())
+    }
+}
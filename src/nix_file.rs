@@ -1,38 +1,97 @@
 //! This is a utility module for interacting with the syntax of Nix files
 
 use crate::location::LineIndex;
+use crate::problem::npv_147::{self, SyntaxError};
+use crate::suppression::Suppressions;
+use crate::validation::Validation::{Failure, Success, SuccessWithWarnings};
+use crate::validation::{self, Validation};
 use anyhow::Context;
 use itertools::Either::{self, Left, Right};
+use itertools::Itertools;
 use relative_path::RelativePathBuf;
+use rnix::ParseError;
 use rnix::ast;
 use rnix::ast::Expr;
+use rnix::ast::HasEntry;
+use rnix::{SyntaxKind, SyntaxNode};
+use rowan::TextRange;
 use rowan::TextSize;
 use rowan::TokenAtOffset;
 use rowan::ast::AstNode;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::fs::read_to_string;
+use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
 
 /// A structure to store parse results of Nix files in memory, making sure that the same file never
 /// has to be parsed twice.
-#[derive(Default)]
 pub struct NixFileStore {
-    entries: HashMap<PathBuf, NixFile>,
+    /// The Nixpkgs directory the files are in, for error messages.
+    nixpkgs_path: PathBuf,
+    entries: HashMap<PathBuf, Validation<NixFile>>,
 }
 
 impl NixFileStore {
+    pub fn new(nixpkgs_path: impl Into<PathBuf>) -> NixFileStore {
+        NixFileStore {
+            nixpkgs_path: nixpkgs_path.into(),
+            entries: HashMap::new(),
+        }
+    }
+
     /// Get the store entry for a Nix file if it exists, otherwise parse the file, insert it into
     /// the store, and return the value.
     ///
     /// Note that this function only gives an `anyhow::Result::Err` for I/O errors.
-    /// A parse error is `anyhow::Result::Ok(Result::Err(error))`
-    pub fn get(&mut self, path: &Path) -> anyhow::Result<&NixFile> {
-        match self.entries.entry(path.to_owned()) {
-            Entry::Occupied(entry) => Ok(entry.into_mut()),
-            Entry::Vacant(entry) => Ok(entry.insert(NixFile::new(path)?)),
-        }
+    /// A file that can't be parsed gives `None`, its syntax errors are reported by `validation`
+    /// instead, so that they're only reported once, no matter how many checks need the file.
+    pub fn get(&mut self, path: &Path) -> anyhow::Result<Option<&NixFile>> {
+        let entry = match self.entries.entry(path.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(NixFile::new(path, &self.nixpkgs_path)?),
+        };
+        Ok(match entry {
+            Success(nix_file) | SuccessWithWarnings(nix_file, _) => Some(nix_file),
+            Failure(_) => None,
+        })
+    }
+
+    /// The syntax errors of all files that were requested so far and that weren't reported by a
+    /// previous call, sorted by path.
+    pub fn validation(&mut self) -> Validation<()> {
+        let mut failures = self
+            .entries
+            .iter_mut()
+            .filter_map(|(path, entry)| match entry {
+                // The entry stays a `Failure`, so that `get` keeps skipping the file
+                Failure(problems) => Some((path, std::mem::take(problems))),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        failures.sort_by_key(|(path, _)| *path);
+        validation::with_problems(
+            failures
+                .into_iter()
+                .flat_map(|(_, problems)| problems)
+                .collect(),
+        )
+    }
+
+    /// All files that were requested so far and could be parsed, sorted by path.
+    pub fn parsed_files(&self) -> Vec<&NixFile> {
+        let mut files = self
+            .entries
+            .values()
+            .filter_map(|entry| match entry {
+                Success(nix_file) | SuccessWithWarnings(nix_file, _) => Some(nix_file),
+                Failure(_) => None,
+            })
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
     }
 }
 
@@ -44,11 +103,14 @@ pub struct NixFile {
     pub path: PathBuf,
     pub syntax_root: rnix::Root,
     pub line_index: LineIndex,
+    /// The inline suppression comments in the file.
+    pub suppressions: Suppressions,
 }
 
 impl NixFile {
-    /// Creates a new `NixFile`, failing for I/O or parse errors.
-    fn new(path: impl AsRef<Path>) -> anyhow::Result<NixFile> {
+    /// Creates a new `NixFile`, failing for I/O errors. Syntax errors give a `Failure` with their
+    /// locations, using a path relative to `nixpkgs_path` for the error message.
+    fn new(path: impl AsRef<Path>, nixpkgs_path: &Path) -> validation::Result<NixFile> {
         let Some(parent_dir) = path.as_ref().parent() else {
             anyhow::bail!("Could not get parent of path {}", path.as_ref().display())
         };
@@ -62,17 +124,234 @@ impl NixFile {
         // correctly, though that uses mainline Nix instead of rnix, so it doesn't give the same
         // errors. In the future we should unify these two checks, ideally moving the other CI
         // check into this tool as well and checking for both mainline Nix and rnix.
-        rnix::Root::parse(&contents)
-            // rnix's `rnix::Parse::ok` returns `Result<_, _>`, so no error is thrown away like it
-            // would be with `std::result::Result::ok`.
-            .ok()
-            .map(|syntax_root| NixFile {
-                parent_dir: parent_dir.to_path_buf(),
-                path: path.as_ref().to_owned(),
-                syntax_root,
-                line_index,
+        let parse = rnix::Root::parse(&contents);
+        if !parse.errors().is_empty() {
+            let relative_path = path
+                .as_ref()
+                .strip_prefix(nixpkgs_path)
+                .unwrap_or(path.as_ref());
+            // rnix often reports the same error multiple times, especially at the end of the file
+            let errors: BTreeSet<_> = parse
+                .errors()
+                .iter()
+                .map(|error| syntax_error(error, &line_index, contents.len().saturating_sub(1)))
+                .collect();
+            return Ok(Failure(vec![
+                npv_147::NixFileHasSyntaxErrors::new(
+                    relative_path.to_string_lossy().into_owned(),
+                    errors.into_iter().collect(),
+                )
+                .into(),
+            ]));
+        }
+
+        let syntax_root = parse.tree();
+        Ok(Success(NixFile {
+            parent_dir: parent_dir.to_path_buf(),
+            path: path.as_ref().to_owned(),
+            suppressions: Suppressions::new(&syntax_root),
+            syntax_root,
+            line_index,
+        }))
+    }
+}
+
+/// Converts an rnix parse error into a syntax error with a line and column. Errors at the end of
+/// the file point to `end`, the index of its last character.
+fn syntax_error(error: &ParseError, line_index: &LineIndex, end: usize) -> SyntaxError {
+    let start = |range: &TextRange| usize::from(range.start());
+    let (index, message) = match error {
+        ParseError::Unexpected(range) => (start(range), "unexpected syntax".to_owned()),
+        ParseError::UnexpectedExtra(range) => (start(range), "unexpected token".to_owned()),
+        ParseError::UnexpectedWanted(got, range, wanted) => (
+            start(range),
+            format!("unexpected {got:?}, expected any of {wanted:?}"),
+        ),
+        ParseError::UnexpectedDoubleBind(range) => (
+            start(range),
+            "the argument pattern is bound twice".to_owned(),
+        ),
+        ParseError::UnexpectedEOF => (end, "unexpected end of file".to_owned()),
+        ParseError::UnexpectedEOFWanted(wanted) => (
+            end,
+            format!("unexpected end of file, expected any of {wanted:?}"),
+        ),
+        ParseError::DuplicatedArgs(range, name) => {
+            (start(range), format!("the argument `{name}` is duplicated"))
+        }
+        ParseError::RecursionLimitExceeded => {
+            (end, "the expression is nested too deeply".to_owned())
+        }
+        // `ParseError` is non-exhaustive
+        error => (end, error.to_string()),
+    };
+    SyntaxError {
+        line: line_index.line(index),
+        column: line_index.column(index),
+        message,
+    }
+}
+
+/// A formal argument of a function like `{ foo, bar ? null }: ...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Formal {
+    pub name: String,
+    /// The line of the argument, for error messages.
+    pub line: usize,
+    /// The string index of the argument, for suppressions.
+    pub index: usize,
+}
+
+impl NixFile {
+    /// Returns the formal arguments of the function the file evaluates to, or `None` if it isn't a
+    /// function taking an attribute set pattern, like `callPackage` expects.
+    pub fn formals(&self) -> Option<Vec<Formal>> {
+        let Some(Expr::Lambda(lambda)) = self.syntax_root.expr() else {
+            return None;
+        };
+        let ast::Param::Pattern(pattern) = lambda.param()? else {
+            return None;
+        };
+        let formals = pattern
+            .pat_entries()
+            .filter_map(|entry| {
+                let index = entry.syntax().text_range().start().into();
+                Some(Formal {
+                    name: entry.ident()?.to_string(),
+                    line: self.line_index.line(index),
+                    index,
+                })
+            })
+            .collect();
+        Some(formals)
+    }
+
+    /// Returns the formal arguments of the function the file evaluates to that are never referred
+    /// to, neither in its body nor in the defaults of other arguments.
+    ///
+    /// If the arguments are bound with `@args`, selecting `args.foo` uses `foo`, while any other
+    /// use of `args` could use all of them, in which case nothing is returned.
+    pub fn unused_formals(&self) -> Vec<Formal> {
+        let Some(Expr::Lambda(lambda)) = self.syntax_root.expr() else {
+            return vec![];
+        };
+        let Some(ast::Param::Pattern(pattern)) = lambda.param() else {
+            return vec![];
+        };
+        let Some(formals) = self.formals() else {
+            return vec![];
+        };
+        let bind = pattern
+            .pat_bind()
+            .and_then(|bind| bind.ident())
+            .map(|ident| ident.to_string());
+
+        let mut used = BTreeSet::new();
+        for (name, reference) in variable_references(lambda.syntax()) {
+            if is_shadowed(&reference, &name, lambda.syntax()) {
+                continue;
+            }
+            if bind.as_ref() == Some(&name) {
+                let Some(attribute) = selected_attribute(&reference) else {
+                    return vec![];
+                };
+                used.insert(attribute);
+            } else {
+                used.insert(name);
+            }
+        }
+
+        formals
+            .into_iter()
+            .filter(|formal| !used.contains(&formal.name))
+            .collect()
+    }
+}
+
+/// Returns all identifiers within a node that refer to variables, along with their names.
+fn variable_references(node: &SyntaxNode) -> impl Iterator<Item = (String, SyntaxNode)> {
+    node.descendants()
+        .filter(|node| node.kind() == SyntaxKind::NODE_IDENT)
+        .filter(|ident| {
+            ident.parent().is_some_and(|parent| match parent.kind() {
+                // Attribute names and bound names
+                SyntaxKind::NODE_ATTRPATH
+                | SyntaxKind::NODE_PAT_BIND
+                | SyntaxKind::NODE_IDENT_PARAM => false,
+                // `{ foo ? bar }` binds `foo`, but refers to the variable `bar`
+                SyntaxKind::NODE_PAT_ENTRY => ast::PatEntry::cast(parent)
+                    .and_then(|entry| entry.default())
+                    .is_some_and(|default| default.syntax() == ident),
+                // `inherit foo;` refers to the variable `foo`, unlike `inherit (bar) foo;`
+                SyntaxKind::NODE_INHERIT => {
+                    ast::Inherit::cast(parent).is_some_and(|inherit| inherit.from().is_none())
+                }
+                _ => true,
             })
-            .with_context(|| format!("Could not parse file {} with rnix", path.as_ref().display()))
+        })
+        .map(|ident| (ident.text().to_string(), ident))
+}
+
+/// Whether a variable reference is bound by a scope between it and the `root` node, like a `let`
+/// binding, a `rec` attribute set or a function argument.
+fn is_shadowed(reference: &SyntaxNode, name: &str, root: &SyntaxNode) -> bool {
+    reference
+        .ancestors()
+        .take_while(|ancestor| ancestor != root)
+        .any(|scope| {
+            // `inherit foo;` in a `let` or `rec` attribute set refers to the outer `foo`
+            let is_own_inherit = reference
+                .parent()
+                .is_some_and(|parent| parent.kind() == SyntaxKind::NODE_INHERIT)
+                && reference
+                    .parent()
+                    .and_then(|parent| parent.parent())
+                    .as_ref()
+                    == Some(&scope);
+            if is_own_inherit {
+                return false;
+            }
+            match Expr::cast(scope) {
+                Some(Expr::Lambda(lambda)) => match lambda.param() {
+                    Some(ast::Param::IdentParam(param)) => {
+                        param.ident().is_some_and(|ident| ident.to_string() == name)
+                    }
+                    Some(ast::Param::Pattern(pattern)) => pattern
+                        .pat_entries()
+                        .filter_map(|entry| entry.ident())
+                        .chain(pattern.pat_bind().and_then(|bind| bind.ident()))
+                        .any(|ident| ident.to_string() == name),
+                    None => false,
+                },
+                Some(Expr::LetIn(let_in)) => binds(&let_in, name),
+                Some(Expr::AttrSet(attr_set)) if attr_set.rec_token().is_some() => {
+                    binds(&attr_set, name)
+                }
+                _ => false,
+            }
+        })
+}
+
+/// Whether `let` bindings or a `rec` attribute set bind a name.
+fn binds(entries: &impl HasEntry, name: &str) -> bool {
+    entries.entries().any(|entry| match entry {
+        ast::Entry::AttrpathValue(attrpath_value) => attrpath_value
+            .attrpath()
+            .and_then(|attrpath| attrpath.attrs().next())
+            .is_some_and(|attr| attr.to_string() == name),
+        ast::Entry::Inherit(inherit) => inherit.attrs().any(|attr| attr.to_string() == name),
+    })
+}
+
+/// Returns the attribute selected from a variable reference like `args.foo`, if it's selected.
+fn selected_attribute(reference: &SyntaxNode) -> Option<String> {
+    let select = ast::Select::cast(reference.parent()?)?;
+    if select.expr()?.syntax() != reference {
+        return None;
+    }
+    match select.attrpath()?.attrs().next()? {
+        ast::Attr::Ident(ident) => Some(ident.to_string()),
+        _ => None,
     }
 }
 
@@ -81,6 +360,10 @@ impl NixFile {
 pub struct CallPackageArgumentInfo {
     /// The relative path of the first argument, or `None` if it's not a path.
     pub relative_path: Option<RelativePathBuf>,
+    /// The attribute names overridden by the second argument, or `None` if it's not an attribute
+    /// set literal. This is empty for `{ }`, in which case the definition is equivalent to the
+    /// automatic one from `pkgs/by-name`.
+    pub overrides: Option<Vec<String>>,
 }
 
 impl NixFile {
@@ -121,7 +404,7 @@ impl NixFile {
     ///
     /// ```rust
     /// Ok((
-    ///   Some(CallPackageArgumentInfo { path = Some("default.nix") }),
+    ///   Some(CallPackageArgumentInfo { path = Some("default.nix"), overrides = Some([]) }),
     ///   "foo = self.callPackage ./default.nix { };",
     /// ))
     /// ```
@@ -285,6 +568,10 @@ impl NixFile {
             anyhow::bail!("apply node doesn't have a lambda: {apply1:?}")
         };
 
+        let Some(arg1) = apply1.argument() else {
+            anyhow::bail!("apply node doesn't have an argument: {apply1:?}")
+        };
+
         // At this point we know it's something like `foo = <fun> <arg>`.
         // For a callPackage, `<fun>` would be `callPackage ./file` and `<arg>` would be `{ }`.
 
@@ -371,6 +658,7 @@ impl NixFile {
         if token.text() == "callPackage" {
             Ok(Some(CallPackageArgumentInfo {
                 relative_path: path,
+                overrides: attribute_names(&arg1),
             }))
         } else {
             Ok(None)
@@ -378,6 +666,79 @@ impl NixFile {
     }
 }
 
+/// Returns the names of the attributes defined by an attribute set literal, or `None` if the
+/// expression is not an attribute set literal.
+///
+/// For nested attribute paths like `foo.bar = ...`, only the first attribute is returned.
+fn attribute_names(expr: &Expr) -> Option<Vec<String>> {
+    let Expr::AttrSet(attr_set) = expr else {
+        return None;
+    };
+    let names = attr_set
+        .entries()
+        .flat_map(|entry| match entry {
+            ast::Entry::AttrpathValue(attrpath_value) => attrpath_value
+                .attrpath()
+                .and_then(|attrpath| attrpath.attrs().next())
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ast::Entry::Inherit(inherit) => inherit.attrs().collect(),
+        })
+        .map(|attr| attr.to_string())
+        .unique()
+        .collect();
+    Some(names)
+}
+
+/// Returns the path literal and the appended string of an expression like `./foo`, `./. + "/foo"`
+/// or `lib.path.append ./. "foo"`, if all parts of it are literals.
+fn path_with_suffix(expr: &Expr) -> Option<(ast::Path, String)> {
+    match expr {
+        Expr::Paren(paren) => path_with_suffix(&paren.expr()?),
+        Expr::BinOp(bin_op) if bin_op.operator() == Some(ast::BinOpKind::Add) => {
+            let (path, suffix) = path_with_suffix(&bin_op.lhs()?)?;
+            Some((path, suffix + &string_literal(&bin_op.rhs()?)?))
+        }
+        // `lib.path.append <path> <string>`, which always adds a `/` in between.
+        Expr::Apply(apply) => {
+            let Expr::Apply(inner_apply) = apply.lambda()? else {
+                return None;
+            };
+            let Expr::Select(select) = inner_apply.lambda()? else {
+                return None;
+            };
+            // Both `lib.path.append` and `path.append`, e.g. after `inherit (lib) path;`
+            let names = select
+                .expr()
+                .into_iter()
+                .map(|expr| expr.to_string())
+                .chain(select.attrpath()?.attrs().map(|attr| attr.to_string()))
+                .collect::<Vec<_>>();
+            if !names.ends_with(&["path".to_owned(), "append".to_owned()]) {
+                return None;
+            }
+            let (path, suffix) = path_with_suffix(&inner_apply.argument()?)?;
+            Some((path, suffix + "/" + &string_literal(&apply.argument()?)?))
+        }
+        _ => Some((ast::Path::cast(expr.syntax().clone())?, String::new())),
+    }
+}
+
+/// Returns the contents of a string literal without any interpolations.
+pub fn string_literal(expr: &Expr) -> Option<String> {
+    let Expr::Str(string) = expr else {
+        return None;
+    };
+    string
+        .normalized_parts()
+        .into_iter()
+        .map(|part| match part {
+            ast::InterpolPart::Literal(literal) => Some(literal),
+            ast::InterpolPart::Interpolation(_) => None,
+        })
+        .collect()
+}
+
 /// The result of trying to statically resolve a Nix path expression.
 pub enum ResolvedPath {
     /// Something like `./foo/${bar}/baz`. This can't be known statically.
@@ -410,6 +771,40 @@ impl NixFile {
     /// Given the path expression `./bar.nix` in `./foo.nix` and an absolute path of the
     /// current directory, the function returns `ResolvedPath::Within(./bar.nix)`.
     pub fn static_resolve_path(&self, node: &ast::Path, relative_to: &Path) -> ResolvedPath {
+        self.static_resolve_path_with_suffix(node, "", relative_to)
+    }
+
+    /// Statically resolves a syntax node if it's a path expression, like `static_resolve_path`.
+    ///
+    /// In addition to path literals, this also resolves path literals with string literals
+    /// appended, like `./. + "/foo"` or `lib.path.append ./. "foo"`. For the path literals within
+    /// such expressions, `None` is returned, so that only the whole expression is resolved. `None`
+    /// is also returned for any other nodes.
+    pub fn static_resolve_path_node(
+        &self,
+        node: &rnix::SyntaxNode,
+        relative_to: &Path,
+    ) -> Option<ResolvedPath> {
+        let is_part_of_path_expression = node
+            .ancestors()
+            .skip(1)
+            .take(2)
+            .filter_map(Expr::cast)
+            .any(|ancestor| path_with_suffix(&ancestor).is_some());
+        if is_part_of_path_expression {
+            return None;
+        }
+
+        let (path, suffix) = path_with_suffix(&Expr::cast(node.clone())?)?;
+        Some(self.static_resolve_path_with_suffix(&path, &suffix, relative_to))
+    }
+
+    fn static_resolve_path_with_suffix(
+        &self,
+        node: &ast::Path,
+        suffix: &str,
+        relative_to: &Path,
+    ) -> ResolvedPath {
         match node {
             ast::Path::PathAbs(_) => return ResolvedPath::AbsolutePath,
             ast::Path::PathHome(_) => return ResolvedPath::HomeRelativePath,
@@ -423,10 +818,14 @@ impl NixFile {
             }
         }
 
-        let text = node.to_string();
+        // Like in Nix, the path literal is made absolute and normalised first, and only then the
+        // string is appended, without a separator in between. So `./. + "foo"` is a sibling of
+        // the current directory, while `./. + "/foo"` is within it.
+        let mut text = normalise(&self.parent_dir.join(node.to_string())).into_os_string();
+        text.push(suffix);
 
-        // Join the file's parent directory and the path expression, then resolve it.
-        match self.parent_dir.join(Path::new(&text)).canonicalize() {
+        // Normalise the result again like Nix does, then resolve it.
+        match normalise(Path::new(&text)).canonicalize() {
             Err(resolution_error) => ResolvedPath::Unresolvable(resolution_error),
             Ok(resolved) => {
                 // Check if it's within relative_to.
@@ -441,6 +840,22 @@ impl NixFile {
     }
 }
 
+/// Normalises an absolute path lexically like Nix does for path values, removing `.` components and
+/// resolving `..` components without following symlinks.
+fn normalise(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -449,6 +864,14 @@ mod tests {
     use pretty_assertions::assert_eq;
     use std::collections::BTreeMap;
 
+    /// Parses a Nix file that's expected to be valid.
+    fn parse(file: &Path) -> anyhow::Result<NixFile> {
+        match NixFile::new(file, Path::new("/"))? {
+            Success(nix_file) => Ok(nix_file),
+            _ => anyhow::bail!("Could not parse file {}", file.display()),
+        }
+    }
+
     #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
     struct Position {
         line: usize,
@@ -489,7 +912,7 @@ mod tests {
 
         std::fs::write(&file, contents)?;
 
-        let nix_file = NixFile::new(&file)?;
+        let nix_file = parse(&file)?;
 
         // These are `builtins.unsafeGetAttrPos` locations for the attributes, generated by saving
         // the Nix file above as `test.nix`, then making this `driver.nix` file.
@@ -583,12 +1006,14 @@ mod tests {
               e = pythonPackages.callPackage ./file.nix { };
               f = callPackage ./file.nix { };
               g = callPackage ({ }: { }) { };
+              h = callPackage ./file.nix { inherit a; b.c = null; b.d = null; };
+              i = callPackage ./file.nix args;
             }
         "};
 
         std::fs::write(&file, contents)?;
 
-        let nix_file = NixFile::new(&file)?;
+        let nix_file = parse(&file)?;
 
         let cases = [
             (2, None),
@@ -599,18 +1024,35 @@ mod tests {
                 6,
                 Some(CallPackageArgumentInfo {
                     relative_path: Some(RelativePathBuf::from("file.nix")),
+                    overrides: Some(vec![]),
                 }),
             ),
             (
                 7,
                 Some(CallPackageArgumentInfo {
                     relative_path: Some(RelativePathBuf::from("file.nix")),
+                    overrides: Some(vec![]),
                 }),
             ),
             (
                 8,
                 Some(CallPackageArgumentInfo {
                     relative_path: None,
+                    overrides: Some(vec![]),
+                }),
+            ),
+            (
+                9,
+                Some(CallPackageArgumentInfo {
+                    relative_path: Some(RelativePathBuf::from("file.nix")),
+                    overrides: Some(vec!["a".to_owned(), "b".to_owned()]),
+                }),
+            ),
+            (
+                10,
+                Some(CallPackageArgumentInfo {
+                    relative_path: Some(RelativePathBuf::from("file.nix")),
+                    overrides: None,
                 }),
             ),
         ];
@@ -624,4 +1066,142 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn resolves_path_expressions() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let dir = temp_dir.path().canonicalize()?.join("pkg");
+        std::fs::create_dir(&dir)?;
+        std::fs::create_dir(dir.join("foo"))?;
+        std::fs::write(dir.join("foo/bar"), "")?;
+        // A sibling of `dir` whose name starts with the name of `dir`
+        std::fs::create_dir(temp_dir.path().join("pkgfoo"))?;
+        let file = dir.join("file.nix");
+        let contents = indoc! {r#"
+            {
+              a = ./. + "/foo";
+              b = lib.path.append ./. "foo/bar";
+              c = ./fo + "o" + "/bar";
+              d = ./. + "/${x}";
+              e = ./. + "/..";
+              f = [ ./foo ];
+              g = ./. + "foo";
+              h = ./foo/.. + "/foo";
+            }
+        "#};
+
+        std::fs::write(&file, contents)?;
+
+        let nix_file = parse(&file)?;
+
+        let actual = nix_file
+            .syntax_root
+            .syntax()
+            .descendants()
+            .filter_map(|node| {
+                let resolved = match nix_file.static_resolve_path_node(&node, &dir)? {
+                    ResolvedPath::Within(path) => path.to_string(),
+                    ResolvedPath::Outside => "<outside>".to_owned(),
+                    _ => "<other>".to_owned(),
+                };
+                Some((node.to_string(), resolved))
+            })
+            .collect::<Vec<_>>();
+
+        let expected = [
+            (r#"./. + "/foo""#, "foo"),
+            (r#"lib.path.append ./. "foo/bar""#, "foo/bar"),
+            (r#"./fo + "o" + "/bar""#, "foo/bar"),
+            // Only the path literal can be resolved statically
+            ("./.", ""),
+            (r#"./. + "/..""#, "<outside>"),
+            ("./foo", "foo"),
+            // Strings are appended to the normalised path without a separator
+            (r#"./. + "foo""#, "<outside>"),
+            (r#"./foo/.. + "/foo""#, "foo"),
+        ]
+        .map(|(text, resolved)| (text.to_owned(), resolved.to_owned()));
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_syntax_errors_once() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let file = temp_dir.path().join("file.nix");
+        std::fs::write(&file, "let\n  a = 1\nin a")?;
+
+        let mut store = NixFileStore::new(temp_dir.path());
+        assert!(store.get(&file)?.is_none());
+        // The file isn't parsed again
+        assert!(store.get(&file)?.is_none());
+
+        let Failure(problems) = store.validation() else {
+            panic!("Expected the syntax error to be reported");
+        };
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [indoc! {"
+                - file.nix: File can't be parsed:
+                  - Line 3, column 1: unexpected TOKEN_IN, expected any of [TOKEN_SEMICOLON]
+                  - Line 3, column 4: unexpected end of file
+                  - Line 3, column 4: unexpected end of file, expected any of [TOKEN_SEMICOLON]"}]
+        );
+        assert!(matches!(store.validation(), Success(())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn detects_unused_formals() -> anyhow::Result<()> {
+        let temp_dir = tests::tempdir()?;
+        let cases = [
+            (
+                indoc! {"
+                    {
+                      a,
+                      b,
+                      c ? b,
+                      d,
+                      e,
+                      f,
+                      g,
+                      h,
+                    }:
+                    let
+                      e = 1;
+                      inherit f;
+                    in
+                    rec {
+                      inherit c e;
+                      g = x: x;
+                      i = d: d;
+                      j = { h ? g }: h;
+                      k = g;
+                    }
+                "},
+                vec!["a", "d", "e", "g", "h"],
+            ),
+            ("{ a, b, ... }@args: args.a // args.c", vec!["b"]),
+            // Dynamic attributes and other uses could use any argument
+            ("{ a, b, ... }@args: args.${\"a\"}", vec![]),
+            ("{ a, b, ... }@args: f args", vec![]),
+            ("a: { b }: a", vec![]),
+        ];
+
+        for (contents, expected) in cases {
+            let file = temp_dir.path().join("file.nix");
+            std::fs::write(&file, contents)?;
+            let nix_file = parse(&file)?;
+            let actual: Vec<_> = nix_file
+                .unused_formals()
+                .into_iter()
+                .map(|formal| formal.name)
+                .collect();
+            assert_eq!(actual, expected, "{contents}");
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,399 @@
+//! Checks for `.patch` and `.diff` files in package directories.
+//!
+//! The parser is intentionally lenient: Like GNU `patch`, anything outside of hunks is ignored, so
+//! patches generated by `git format-patch` with commit messages are supported.
+//! Hunks are validated against the line counts in their headers. Other formats `patch` and
+//! `git apply` support, like git binary patches, context diffs and normal diffs, only count as
+//! changes without being validated.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use relative_path::RelativePath;
+
+use crate::problem::npv_132::Malformation;
+use crate::problem::npv_133::NonPortability;
+use crate::problem::{Problem, npv_132, npv_133};
+use crate::validation;
+
+/// The file extensions of patch files.
+pub const PATCH_EXTENSIONS: [&str; 2] = ["patch", "diff"];
+
+/// Check that a patch file is a well-formed unified diff that applies on any system.
+pub fn check_patch_file(
+    relative_package_dir: &RelativePath,
+    absolute_package_dir: &Path,
+    subpath: &RelativePath,
+) -> validation::Result<()> {
+    let path = subpath.to_path(absolute_package_dir);
+    let contents =
+        fs::read(&path).with_context(|| format!("Could not read patch file {}", path.display()))?;
+
+    let (malformations, non_portabilities) = parse(&contents);
+    let problems = malformations
+        .into_iter()
+        .map(|malformation| -> Problem {
+            npv_132::PatchFileIsMalformed::new(relative_package_dir, subpath, malformation).into()
+        })
+        .chain(non_portabilities.into_iter().map(|non_portability| {
+            npv_133::PatchFileIsNonPortable::new(relative_package_dir, subpath, non_portability)
+                .into()
+        }))
+        .collect();
+    Ok(validation::with_problems(problems))
+}
+
+/// Parses the contents of a patch file, returning all malformations and non-portable contents.
+fn parse(contents: &[u8]) -> (Vec<Malformation>, Vec<NonPortability>) {
+    let mut malformations = vec![];
+    let mut non_portabilities = vec![];
+
+    let mut lines: Vec<&[u8]> = contents.split(|byte| *byte == b'\n').collect();
+    // A trailing newline doesn't start another line
+    if lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let crlf = lines.iter().filter(|line| line.ends_with(b"\r")).count();
+    let lf = lines.len() - crlf;
+    let lines: Vec<&[u8]> = lines
+        .into_iter()
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+
+    let mut has_changes = false;
+    let mut store_path_line = None;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        // Git patches may only rename files, change their mode or contain binary patches, without
+        // any hunks
+        if line.starts_with(b"diff --git ") || is_other_hunk_header(line) {
+            has_changes = true;
+        }
+        if !line.starts_with(b"@@") {
+            continue;
+        }
+
+        let header_line = index;
+        let Some((expected_old, expected_new)) = parse_hunk_header(&String::from_utf8_lossy(line))
+        else {
+            malformations.push(Malformation::InvalidHunkHeader { line: header_line });
+            continue;
+        };
+        has_changes = true;
+
+        // Combined diffs of merges have one column per parent, each with its own old lines
+        let parents = expected_old.len();
+        let mut actual_old = vec![0; parents];
+        let mut actual_new = 0;
+        while (actual_old != expected_old || actual_new < expected_new) && index < lines.len() {
+            let line = lines[index];
+            // The file header of the next file, which is more likely than the hunk containing a
+            // removed line starting with "-- " followed by an added line starting with "++ "
+            if line.starts_with(b"--- ")
+                && lines
+                    .get(index + 1)
+                    .is_some_and(|next| next.starts_with(b"+++ "))
+            {
+                break;
+            }
+            // "\ No newline at end of file"
+            if line.starts_with(b"\\") {
+                index += 1;
+                continue;
+            }
+            // Some tools strip the trailing space of empty context lines, which `patch` accepts
+            let columns = if line.is_empty() {
+                &vec![b' '; parents][..]
+            } else {
+                match line.get(..parents) {
+                    Some(columns) if columns.iter().all(|c| b" -+".contains(c)) => columns,
+                    _ => break,
+                }
+            };
+            // Lines removed from some parent are only in those parents, other lines are in the
+            // result and all parents they weren't added to
+            let removed = columns.contains(&b'-');
+            for (column, old) in columns.iter().zip(&mut actual_old) {
+                if *column == b'-' || !removed && *column == b' ' {
+                    *old += 1;
+                }
+            }
+            if !removed {
+                actual_new += 1;
+            }
+            if store_path_line.is_none() && contains_store_path(line) {
+                store_path_line = Some(index + 1);
+            }
+            index += 1;
+        }
+
+        // Only one parent is reported for combined diffs, the first one that doesn't match
+        let mismatch = (0..parents)
+            .find(|&parent| actual_old[parent] != expected_old[parent])
+            .or((actual_new != expected_new).then_some(0));
+        if let Some(parent) = mismatch {
+            malformations.push(Malformation::HunkLengthMismatch {
+                line: header_line,
+                expected_old: expected_old[parent],
+                expected_new,
+                actual_old: actual_old[parent],
+                actual_new,
+            });
+        }
+    }
+
+    if !has_changes && malformations.is_empty() {
+        malformations.push(Malformation::Empty);
+    }
+    if let Some(line) = store_path_line {
+        non_portabilities.push(NonPortability::StorePath { line });
+    }
+    if crlf > 0 && lf > 0 {
+        non_portabilities.push(NonPortability::MixedLineEndings { crlf, lf });
+    }
+
+    (malformations, non_portabilities)
+}
+
+/// Whether a line starts a hunk in a format other than unified diffs, which aren't validated: The
+/// separator of context diff hunks, or a normal diff command like `12,14c12,15`.
+fn is_other_hunk_header(line: &[u8]) -> bool {
+    if line == b"***************" {
+        return true;
+    }
+    let Some(command) = line.iter().position(|byte| b"acd".contains(byte)) else {
+        return false;
+    };
+    let is_range = |range: &[u8]| {
+        let mut parts = range.splitn(2, |byte| *byte == b',');
+        parts.all(|part| !part.is_empty() && part.iter().all(u8::is_ascii_digit))
+    };
+    is_range(&line[..command]) && is_range(&line[command + 1..])
+}
+
+/// Parses a hunk header like `@@ -1,3 +1,4 @@ int main()`, returning the number of old and new
+/// lines. Combined diffs of merges have a range and an additional `@` for each parent, like
+/// `@@@ -1,3 -1,2 +1,4 @@@`, so the old number of lines is returned for each parent.
+fn parse_hunk_header(line: &str) -> Option<(Vec<u64>, u64)> {
+    let markers = line.len() - line.trim_start_matches('@').len();
+    if markers < 2 {
+        return None;
+    }
+    let marker = &line[..markers];
+    let rest = line[markers..].strip_prefix(' ')?;
+    let (ranges, _section) = rest.split_once(&format!(" {marker}"))?;
+
+    let mut ranges = ranges.split(' ');
+    let old = (&mut ranges)
+        .take(markers - 1)
+        .map(|range| range_length(range.strip_prefix('-')?))
+        .collect::<Option<Vec<_>>>()?;
+    let new = range_length(ranges.next()?.strip_prefix('+')?)?;
+    if old.len() != markers - 1 || ranges.next().is_some() {
+        return None;
+    }
+    Some((old, new))
+}
+
+/// Parses a hunk range like `1,3` or `1`, returning its length.
+fn range_length(range: &str) -> Option<u64> {
+    let (start, length) = match range.split_once(',') {
+        Some((start, length)) => (start, length.parse().ok()?),
+        None => (range, 1),
+    };
+    start.parse::<u64>().ok()?;
+    Some(length)
+}
+
+fn contains_store_path(line: &[u8]) -> bool {
+    const STORE_DIR: &[u8] = b"/nix/store/";
+    line.windows(STORE_DIR.len())
+        .any(|window| window == STORE_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_patches() {
+        let cases: [(&str, Vec<Malformation>, Vec<NonPortability>); 13] = [
+            (
+                indoc! {"
+                    From 1234 Mon Sep 17 00:00:00 2001
+                    Subject: [PATCH] Fix build
+
+                    ---
+                    diff --git a/foo.c b/foo.c
+                    --- a/foo.c
+                    +++ b/foo.c
+                    @@ -1,3 +1,3 @@ int main()
+                     #include <stdio.h>
+                    -int main() {
+                    +int main(void) {
+
+                    @@ -10 +10,0 @@
+                    -  return 1;
+                    \\ No newline at end of file
+                    --
+                    2.44.0
+                "},
+                vec![],
+                vec![],
+            ),
+            ("", vec![Malformation::Empty], vec![]),
+            ("Just some text\n", vec![Malformation::Empty], vec![]),
+            (
+                indoc! {"
+                    diff --git a/foo b/bar
+                    similarity index 100%
+                    rename from foo
+                    rename to bar
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    --- a/foo.c
+                    +++ b/foo.c
+                    @@ -1,3 +1,4 @@
+                     #include <stdio.h>
+                    +#include <stdlib.h>
+                     int main() {
+                    --- a/bar.c
+                    +++ b/bar.c
+                    @@ -a +b @@
+                "},
+                vec![
+                    Malformation::HunkLengthMismatch {
+                        line: 3,
+                        expected_old: 3,
+                        expected_new: 4,
+                        actual_old: 2,
+                        actual_new: 3,
+                    },
+                    Malformation::InvalidHunkHeader { line: 9 },
+                ],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    --- a/foo.c
+                    +++ b/foo.c
+                    @@ -1,2 +1,2 @@
+                    -int a;
+                    +int b;
+                    -int c;
+                "},
+                vec![Malformation::HunkLengthMismatch {
+                    line: 3,
+                    expected_old: 2,
+                    expected_new: 2,
+                    actual_old: 2,
+                    actual_new: 1,
+                }],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    --- a/configure
+                    +++ b/configure
+                    @@ -1 +1 @@
+                    -CC=gcc
+                    +CC=/nix/store/00000000000000000000000000000000-gcc/bin/gcc
+                "},
+                vec![],
+                vec![NonPortability::StorePath { line: 5 }],
+            ),
+            (
+                indoc! {"
+                    diff --cc foo.c
+                    index 1234567,89abcde..0123456
+                    --- a/foo.c
+                    +++ b/foo.c
+                    @@@ -1,2 -1,2 +1,3 @@@
+                      #include <stdio.h>
+                    - int a;
+                     -int b;
+                    ++int c;
+                    ++ int d;
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    diff --git a/icon.png b/icon.png
+                    new file mode 100644
+                    index 0000000..1234567
+                    GIT binary patch
+                    literal 5
+                    McmZ?wbhEJ200000
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    *** foo.c.orig
+                    --- foo.c
+                    ***************
+                    *** 1 ****
+                    ! int main() {
+                    --- 1 ----
+                    ! int main(void) {
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    1,2c1
+                    < int a;
+                    < int b;
+                    ---
+                    > int c;
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                indoc! {"
+                    Fix the build with newer compilers
+
+                    Index: foo-1.0/foo.c
+                    ===================================================================
+                    --- foo-1.0.orig/foo.c
+                    +++ foo-1.0/foo.c
+                    @@ -1 +1 @@
+                    -int main() {
+                    +int main(void) {
+                "},
+                vec![],
+                vec![],
+            ),
+            (
+                "--- a/foo.bat\r\n+++ b/foo.bat\r\n@@ -1 +1 @@\n-echo a\r\n+echo b\r\n",
+                vec![],
+                vec![NonPortability::MixedLineEndings { crlf: 4, lf: 1 }],
+            ),
+        ];
+
+        for (contents, expected_malformations, expected_non_portabilities) in cases {
+            let (malformations, non_portabilities) = parse(contents.as_bytes());
+            assert_eq!(
+                (malformations, non_portabilities),
+                (expected_malformations, expected_non_portabilities),
+                "Patch:\n{contents}"
+            );
+        }
+    }
+}
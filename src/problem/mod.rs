@@ -1,12 +1,17 @@
 use std::fmt;
 
 use derive_enum_from_into::EnumFrom;
+use serde::Deserialize;
 
 pub mod npv_100;
 pub mod npv_101;
+pub mod npv_105;
 pub mod npv_109;
 pub mod npv_110;
 pub mod npv_111;
+pub mod npv_112;
+pub mod npv_113;
+pub mod npv_114;
 
 pub mod npv_120;
 pub mod npv_121;
@@ -17,6 +22,12 @@ pub mod npv_125;
 pub mod npv_126;
 pub mod npv_127;
 pub mod npv_128;
+pub mod npv_129;
+pub mod npv_130;
+pub mod npv_131;
+pub mod npv_132;
+pub mod npv_133;
+pub mod npv_134;
 
 pub mod npv_140;
 pub mod npv_141;
@@ -25,6 +36,7 @@ pub mod npv_143;
 pub mod npv_144;
 pub mod npv_145;
 pub mod npv_146;
+pub mod npv_147;
 
 pub mod npv_160;
 pub mod npv_162;
@@ -32,19 +44,362 @@ pub mod npv_164;
 pub mod npv_165;
 pub mod npv_166;
 pub mod npv_167;
+pub mod npv_168;
+pub mod npv_169;
 
 pub mod npv_170;
+pub mod npv_171;
+pub mod npv_172;
+pub mod npv_173;
+pub mod npv_174;
+pub mod npv_175;
+pub mod npv_176;
+pub mod npv_177;
 
 const WIKI_BASE_URL: &str = "https://github.com/NixOS/nixpkgs-vet/wiki";
 
+/// Which part of nixpkgs-vet performs a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, derive_more::Display)]
+pub enum Category {
+    /// The file structure of `pkgs/by-name`.
+    #[display("structure")]
+    Structure,
+    /// The path references of Nix files in `pkgs/by-name`.
+    #[display("references")]
+    References,
+    /// The evaluation of `pkgs/by-name` attributes.
+    #[display("evaluation")]
+    Evaluation,
+    /// The ratchet checks between the base and main Nixpkgs.
+    #[display("ratchet")]
+    Ratchet,
+    /// All Nix files in Nixpkgs.
+    #[display("files")]
+    Files,
+}
+
+/// Information about the check reporting a problem code, see `nixpkgs-vet list-checks`.
+#[derive(Debug)]
+pub struct Check {
+    pub code: &'static str,
+    pub category: Category,
+    pub default_severity: Severity,
+    /// Whether existing instances of the problem are allowed, see ../README.md#ratchet-checks.
+    pub ratchet: bool,
+    /// Whether nixpkgs-vet can fix the problem automatically.
+    pub auto_fix: bool,
+    /// The documentation, embedded in the binary so that it's available offline and matches the
+    /// version in use. See `nixpkgs-vet explain`.
+    pub explanation: &'static str,
+}
+
+impl Check {
+    const fn new(code: &'static str, category: Category, explanation: &'static str) -> Self {
+        Self {
+            code,
+            category,
+            default_severity: Severity::Error,
+            ratchet: matches!(category, Category::Ratchet),
+            auto_fix: false,
+            explanation,
+        }
+    }
+
+    const fn with_auto_fix(self) -> Self {
+        Self {
+            auto_fix: true,
+            ..self
+        }
+    }
+
+    const fn with_default_severity(self, default_severity: Severity) -> Self {
+        Self {
+            default_severity,
+            ..self
+        }
+    }
+
+    /// The short description of the problem, from the heading of its documentation.
+    pub fn title(&self) -> &'static str {
+        let heading = self.explanation.lines().next().unwrap_or_default();
+        heading.split_once(": ").map_or(heading, |(_, title)| title)
+    }
+}
+
+/// All problem codes that can be reported, in order.
+pub const CHECKS: &[Check] = &[
+    Check::new(
+        "NPV-100",
+        Category::Evaluation,
+        include_str!("docs/NPV-100.md"),
+    ),
+    Check::new(
+        "NPV-101",
+        Category::Evaluation,
+        include_str!("docs/NPV-101.md"),
+    ),
+    Check::new(
+        "NPV-105",
+        Category::Evaluation,
+        include_str!("docs/NPV-105.md"),
+    ),
+    Check::new(
+        "NPV-109",
+        Category::Structure,
+        include_str!("docs/NPV-109.md"),
+    ),
+    Check::new(
+        "NPV-110",
+        Category::Structure,
+        include_str!("docs/NPV-110.md"),
+    ),
+    Check::new(
+        "NPV-111",
+        Category::Structure,
+        include_str!("docs/NPV-111.md"),
+    ),
+    Check::new(
+        "NPV-112",
+        Category::Structure,
+        include_str!("docs/NPV-112.md"),
+    ),
+    Check::new(
+        "NPV-113",
+        Category::Structure,
+        include_str!("docs/NPV-113.md"),
+    ),
+    Check::new(
+        "NPV-114",
+        Category::Structure,
+        include_str!("docs/NPV-114.md"),
+    ),
+    Check::new(
+        "NPV-120",
+        Category::Evaluation,
+        include_str!("docs/NPV-120.md"),
+    ),
+    Check::new(
+        "NPV-121",
+        Category::References,
+        include_str!("docs/NPV-121.md"),
+    ),
+    Check::new(
+        "NPV-122",
+        Category::References,
+        include_str!("docs/NPV-122.md"),
+    ),
+    Check::new(
+        "NPV-123",
+        Category::References,
+        include_str!("docs/NPV-123.md"),
+    ),
+    Check::new(
+        "NPV-124",
+        Category::References,
+        include_str!("docs/NPV-124.md"),
+    ),
+    Check::new(
+        "NPV-125",
+        Category::References,
+        include_str!("docs/NPV-125.md"),
+    ),
+    Check::new(
+        "NPV-126",
+        Category::References,
+        include_str!("docs/NPV-126.md"),
+    ),
+    Check::new(
+        "NPV-127",
+        Category::References,
+        include_str!("docs/NPV-127.md"),
+    ),
+    Check::new(
+        "NPV-128",
+        Category::References,
+        include_str!("docs/NPV-128.md"),
+    ),
+    Check::new(
+        "NPV-129",
+        Category::References,
+        include_str!("docs/NPV-129.md"),
+    )
+    .with_default_severity(Severity::Warning),
+    Check::new(
+        "NPV-130",
+        Category::References,
+        include_str!("docs/NPV-130.md"),
+    )
+    .with_default_severity(Severity::Warning),
+    Check::new(
+        "NPV-131",
+        Category::References,
+        include_str!("docs/NPV-131.md"),
+    ),
+    Check::new(
+        "NPV-132",
+        Category::References,
+        include_str!("docs/NPV-132.md"),
+    ),
+    Check::new(
+        "NPV-133",
+        Category::References,
+        include_str!("docs/NPV-133.md"),
+    )
+    .with_default_severity(Severity::Warning),
+    Check::new(
+        "NPV-134",
+        Category::References,
+        include_str!("docs/NPV-134.md"),
+    )
+    .with_default_severity(Severity::Warning),
+    Check::new(
+        "NPV-140",
+        Category::Structure,
+        include_str!("docs/NPV-140.md"),
+    ),
+    Check::new(
+        "NPV-141",
+        Category::Structure,
+        include_str!("docs/NPV-141.md"),
+    ),
+    Check::new(
+        "NPV-142",
+        Category::Structure,
+        include_str!("docs/NPV-142.md"),
+    ),
+    Check::new(
+        "NPV-143",
+        Category::Structure,
+        include_str!("docs/NPV-143.md"),
+    ),
+    Check::new(
+        "NPV-144",
+        Category::Structure,
+        include_str!("docs/NPV-144.md"),
+    ),
+    Check::new("NPV-145", Category::Files, include_str!("docs/NPV-145.md")),
+    Check::new("NPV-146", Category::Files, include_str!("docs/NPV-146.md")),
+    Check::new("NPV-147", Category::Files, include_str!("docs/NPV-147.md")),
+    // Both can be fixed with `nixpkgs-vet migrate`
+    Check::new(
+        "NPV-160",
+        Category::Ratchet,
+        include_str!("docs/NPV-160.md"),
+    )
+    .with_auto_fix(),
+    Check::new(
+        "NPV-162",
+        Category::Ratchet,
+        include_str!("docs/NPV-162.md"),
+    )
+    .with_auto_fix(),
+    Check::new(
+        "NPV-164",
+        Category::Ratchet,
+        include_str!("docs/NPV-164.md"),
+    ),
+    Check::new(
+        "NPV-165",
+        Category::Ratchet,
+        include_str!("docs/NPV-165.md"),
+    ),
+    Check::new(
+        "NPV-166",
+        Category::Ratchet,
+        include_str!("docs/NPV-166.md"),
+    ),
+    Check::new(
+        "NPV-167",
+        Category::Ratchet,
+        include_str!("docs/NPV-167.md"),
+    ),
+    Check::new(
+        "NPV-168",
+        Category::Ratchet,
+        include_str!("docs/NPV-168.md"),
+    ),
+    Check::new(
+        "NPV-169",
+        Category::Ratchet,
+        include_str!("docs/NPV-169.md"),
+    ),
+    Check::new("NPV-170", Category::Files, include_str!("docs/NPV-170.md")),
+    Check::new("NPV-171", Category::Files, include_str!("docs/NPV-171.md")),
+    Check::new("NPV-172", Category::Files, include_str!("docs/NPV-172.md")),
+    Check::new(
+        "NPV-173",
+        Category::Ratchet,
+        include_str!("docs/NPV-173.md"),
+    ),
+    Check::new(
+        "NPV-174",
+        Category::Ratchet,
+        include_str!("docs/NPV-174.md"),
+    ),
+    Check::new(
+        "NPV-175",
+        Category::Ratchet,
+        include_str!("docs/NPV-175.md"),
+    ),
+    Check::new(
+        "NPV-176",
+        Category::Ratchet,
+        include_str!("docs/NPV-176.md"),
+    ),
+    Check::new(
+        "NPV-177",
+        Category::Ratchet,
+        include_str!("docs/NPV-177.md"),
+    ),
+];
+
+/// Returns the check for a problem code (e.g. "NPV-100"), if there is such a code.
+pub fn check(code: &str) -> Option<&'static Check> {
+    CHECKS
+        .iter()
+        .find(|check| check.code.eq_ignore_ascii_case(code))
+}
+
+/// How severe a problem is. Only errors fail the validation, other problems are just reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, derive_more::Display)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[display("note")]
+    Note,
+    #[display("warning")]
+    Warning,
+    #[display("error")]
+    Error,
+}
+
+/// A problem found in Nixpkgs, along with its severity.
+#[derive(Clone, Debug)]
+pub struct Problem {
+    kind: ProblemKind,
+    severity: Severity,
+}
+
+impl<Kind: Into<ProblemKind>> From<Kind> for Problem {
+    fn from(kind: Kind) -> Self {
+        let kind = kind.into();
+        Self {
+            severity: kind.default_severity(),
+            kind,
+        }
+    }
+}
+
 #[derive(Clone, Debug, EnumFrom)]
-pub enum Problem {
+pub enum ProblemKind {
     /// NPV-100: attribute is not defined but it should be defined automatically
     ByNameUndefinedAttribute(npv_100::ByNameUndefinedAttribute),
 
     /// NPV-101: attribute is not a derivation
     ByNameNonDerivation(npv_101::ByNameNonDerivation),
 
+    /// NPV-105: redundant manual definition of by-name package
+    ByNameOverrideContainsEmptyArgument(npv_105::ByNameOverrideContainsEmptyArgument),
+
     /// NPV-109: by-name shard is not a directory
     ByNameShardIsNotDirectory(npv_109::ByNameShardIsNotDirectory),
 
@@ -54,6 +409,15 @@ pub enum Problem {
     /// NPV-111: by-name shard is case-sensitive duplicate
     ByNameShardIsCaseSensitiveDuplicate(npv_111::ByNameShardIsCaseSensitiveDuplicate),
 
+    /// NPV-112: required by-name file is missing or empty
+    ByNameRequiredFileIsMissingOrEmpty(npv_112::ByNameRequiredFileIsMissingOrEmpty),
+
+    /// NPV-113: hidden file in by-name
+    ByNameContainsHiddenFile(npv_113::ByNameContainsHiddenFile),
+
+    /// NPV-114: by-name package names only differ in `-` and `_`
+    ByNamePackageNamesCollide(npv_114::ByNamePackageNamesCollide),
+
     /// NPV-120: Nix evaluation failed
     NixEvalError(npv_120::NixEvalError),
 
@@ -81,6 +445,24 @@ pub enum Problem {
     /// NPV-128: Nix file contains home-relative path expression
     NixFileContainsHomeRelativePath(npv_128::NixFileContainsHomeRelativePath),
 
+    /// NPV-129: Package contains unused Nix file
+    PackageContainsUnusedNixFile(npv_129::PackageContainsUnusedNixFile),
+
+    /// NPV-130: Package contains unused file
+    PackageContainsUnusedFile(npv_130::PackageContainsUnusedFile),
+
+    /// NPV-131: Package contains large or binary file
+    PackageContainsLargeOrBinaryFile(npv_131::PackageContainsLargeOrBinaryFile),
+
+    /// NPV-132: Patch file is malformed
+    PatchFileIsMalformed(npv_132::PatchFileIsMalformed),
+
+    /// NPV-133: Patch file is non-portable
+    PatchFileIsNonPortable(npv_133::PatchFileIsNonPortable),
+
+    /// NPV-134: Package function argument is unused
+    PackageFunctionArgumentIsUnused(npv_134::PackageFunctionArgumentIsUnused),
+
     /// NPV-140: Package directory is not directory
     PackageDirectoryIsNotDirectory(npv_140::PackageDirectoryIsNotDirectory),
 
@@ -102,6 +484,9 @@ pub enum Problem {
     /// NPV-146: Nix file has shebang but is not executable
     NixFileHasShebangButNotExecutable(npv_146::NixFileHasShebangButNotExecutable),
 
+    /// NPV-147: Nix file has syntax errors
+    NixFileHasSyntaxErrors(npv_147::NixFileHasSyntaxErrors),
+
     /// NPV-160: top-level package moved out of by-name
     TopLevelPackageMovedOutOfByName(npv_160::TopLevelPackageMovedOutOfByName),
 
@@ -122,19 +507,81 @@ pub enum Problem {
     /// NPV-167: top-level package disabled __structuredAttrs
     TopLevelPackageDisabledStructuredAttrs(npv_167::TopLevelPackageDisabledStructuredAttrs),
 
+    /// NPV-168: new by-name package name must match pname
+    NewByNamePackageNameMismatch(npv_168::NewByNamePackageNameMismatch),
+
+    /// NPV-169: by-name package name diverged from pname
+    ByNamePackageNameDiverged(npv_169::ByNamePackageNameDiverged),
+
     /// NPV-170: nix files should not contain useless escapes
     NixFileContainsUselessEscape(npv_170::NixFileContainsUselessEscape),
+
+    /// NPV-171: nix file contains an invalid suppression comment
+    NixFileContainsInvalidSuppression(npv_171::NixFileContainsInvalidSuppression),
+
+    /// NPV-172: nix file contains a suppression comment that doesn't suppress anything
+    NixFileContainsUnusedSuppression(npv_172::NixFileContainsUnusedSuppression),
+
+    /// NPV-173: Nix file uses deprecated fetcher hash
+    NixFileUsesDeprecatedFetcherHash(npv_173::NixFileUsesDeprecatedFetcherHash),
+
+    /// NPV-174: Nix file uses rec derivation
+    NixFileUsesRecDerivation(npv_174::NixFileUsesRecDerivation),
+
+    /// NPV-175: Nix file uses deprecated attribute
+    NixFileUsesDeprecatedAttribute(npv_175::NixFileUsesDeprecatedAttribute),
+
+    /// NPV-176: New by-name package uses alias argument
+    NewByNamePackageUsesAliasArgument(npv_176::NewByNamePackageUsesAliasArgument),
+
+    /// NPV-177: By-name package started using alias argument
+    ByNamePackageStartedUsingAliasArgument(npv_177::ByNamePackageStartedUsingAliasArgument),
 }
 
 impl Problem {
     /// Returns the NPV error code for this problem (e.g. "NPV-100").
     pub fn npv_code(&self) -> &'static str {
+        self.kind.npv_code()
+    }
+
+    /// Returns how severe this problem is.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the same problem, but with a different severity.
+    pub fn with_severity(self, severity: Severity) -> Self {
+        Self { severity, ..self }
+    }
+
+    /// Whether this problem fails the validation.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Returns the wiki URL for this problem's documentation.
+    pub fn wiki_url(&self) -> String {
+        format!("{WIKI_BASE_URL}/{}", self.npv_code())
+    }
+}
+
+impl ProblemKind {
+    /// Returns the severity problems of this kind have unless configured otherwise.
+    fn default_severity(&self) -> Severity {
+        check(self.npv_code()).map_or(Severity::Error, |check| check.default_severity)
+    }
+
+    fn npv_code(&self) -> &'static str {
         match self {
             Self::ByNameUndefinedAttribute(..) => "NPV-100",
             Self::ByNameNonDerivation(..) => "NPV-101",
+            Self::ByNameOverrideContainsEmptyArgument(..) => "NPV-105",
             Self::ByNameShardIsNotDirectory(..) => "NPV-109",
             Self::ByNameShardIsInvalid(..) => "NPV-110",
             Self::ByNameShardIsCaseSensitiveDuplicate(..) => "NPV-111",
+            Self::ByNameRequiredFileIsMissingOrEmpty(..) => "NPV-112",
+            Self::ByNameContainsHiddenFile(..) => "NPV-113",
+            Self::ByNamePackageNamesCollide(..) => "NPV-114",
             Self::NixEvalError(..) => "NPV-120",
             Self::NixFileContainsPathInterpolation(..) => "NPV-121",
             Self::NixFileContainsSearchPath(..) => "NPV-122",
@@ -144,6 +591,12 @@ impl Problem {
             Self::PackageContainsUnresolvableSymlink(..) => "NPV-126",
             Self::NixFileContainsAbsolutePath(..) => "NPV-127",
             Self::NixFileContainsHomeRelativePath(..) => "NPV-128",
+            Self::PackageContainsUnusedNixFile(..) => "NPV-129",
+            Self::PackageContainsUnusedFile(..) => "NPV-130",
+            Self::PackageContainsLargeOrBinaryFile(..) => "NPV-131",
+            Self::PatchFileIsMalformed(..) => "NPV-132",
+            Self::PatchFileIsNonPortable(..) => "NPV-133",
+            Self::PackageFunctionArgumentIsUnused(..) => "NPV-134",
             Self::PackageDirectoryIsNotDirectory(..) => "NPV-140",
             Self::InvalidPackageDirectoryName(..) => "NPV-141",
             Self::PackageInWrongShard(..) => "NPV-142",
@@ -151,30 +604,45 @@ impl Problem {
             Self::PackageNixIsNotFile(..) => "NPV-144",
             Self::NixFileIsExecutableWithoutShebang(..) => "NPV-145",
             Self::NixFileHasShebangButNotExecutable(..) => "NPV-146",
+            Self::NixFileHasSyntaxErrors(..) => "NPV-147",
             Self::TopLevelPackageMovedOutOfByName(..) => "NPV-160",
             Self::NewTopLevelPackageShouldBeByName(..) => "NPV-162",
             Self::NewTopLevelPackageMustEnableStrictDeps(..) => "NPV-164",
             Self::TopLevelPackageDisabledStrictDeps(..) => "NPV-165",
             Self::NewTopLevelPackageMustEnableStructuredAttrs(..) => "NPV-166",
             Self::TopLevelPackageDisabledStructuredAttrs(..) => "NPV-167",
+            Self::NewByNamePackageNameMismatch(..) => "NPV-168",
+            Self::ByNamePackageNameDiverged(..) => "NPV-169",
             Self::NixFileContainsUselessEscape(..) => "NPV-170",
+            Self::NixFileContainsInvalidSuppression(..) => "NPV-171",
+            Self::NixFileContainsUnusedSuppression(..) => "NPV-172",
+            Self::NixFileUsesDeprecatedFetcherHash(..) => "NPV-173",
+            Self::NixFileUsesRecDerivation(..) => "NPV-174",
+            Self::NixFileUsesDeprecatedAttribute(..) => "NPV-175",
+            Self::NewByNamePackageUsesAliasArgument(..) => "NPV-176",
+            Self::ByNamePackageStartedUsingAliasArgument(..) => "NPV-177",
         }
     }
+}
 
-    /// Returns the wiki URL for this problem's documentation.
-    pub fn wiki_url(&self) -> String {
-        format!("{WIKI_BASE_URL}/{}", self.npv_code())
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
     }
 }
 
-impl fmt::Display for Problem {
+impl fmt::Display for ProblemKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::ByNameUndefinedAttribute(inner) => inner.fmt(f),
             Self::ByNameNonDerivation(inner) => inner.fmt(f),
+            Self::ByNameOverrideContainsEmptyArgument(inner) => inner.fmt(f),
             Self::ByNameShardIsNotDirectory(inner) => inner.fmt(f),
             Self::ByNameShardIsInvalid(inner) => inner.fmt(f),
             Self::ByNameShardIsCaseSensitiveDuplicate(inner) => inner.fmt(f),
+            Self::ByNameRequiredFileIsMissingOrEmpty(inner) => inner.fmt(f),
+            Self::ByNameContainsHiddenFile(inner) => inner.fmt(f),
+            Self::ByNamePackageNamesCollide(inner) => inner.fmt(f),
             Self::NixEvalError(inner) => inner.fmt(f),
             Self::NixFileContainsPathInterpolation(inner) => inner.fmt(f),
             Self::NixFileContainsSearchPath(inner) => inner.fmt(f),
@@ -184,6 +652,12 @@ impl fmt::Display for Problem {
             Self::PackageContainsUnresolvableSymlink(inner) => inner.fmt(f),
             Self::NixFileContainsAbsolutePath(inner) => inner.fmt(f),
             Self::NixFileContainsHomeRelativePath(inner) => inner.fmt(f),
+            Self::PackageContainsUnusedNixFile(inner) => inner.fmt(f),
+            Self::PackageContainsUnusedFile(inner) => inner.fmt(f),
+            Self::PackageContainsLargeOrBinaryFile(inner) => inner.fmt(f),
+            Self::PatchFileIsMalformed(inner) => inner.fmt(f),
+            Self::PatchFileIsNonPortable(inner) => inner.fmt(f),
+            Self::PackageFunctionArgumentIsUnused(inner) => inner.fmt(f),
             Self::PackageDirectoryIsNotDirectory(inner) => inner.fmt(f),
             Self::InvalidPackageDirectoryName(inner) => inner.fmt(f),
             Self::PackageInWrongShard(inner) => inner.fmt(f),
@@ -191,13 +665,45 @@ impl fmt::Display for Problem {
             Self::PackageNixIsNotFile(inner) => inner.fmt(f),
             Self::NixFileIsExecutableWithoutShebang(inner) => inner.fmt(f),
             Self::NixFileHasShebangButNotExecutable(inner) => inner.fmt(f),
+            Self::NixFileHasSyntaxErrors(inner) => inner.fmt(f),
             Self::TopLevelPackageMovedOutOfByName(inner) => inner.fmt(f),
             Self::NewTopLevelPackageShouldBeByName(inner) => inner.fmt(f),
             Self::NewTopLevelPackageMustEnableStrictDeps(inner) => inner.fmt(f),
             Self::TopLevelPackageDisabledStrictDeps(inner) => inner.fmt(f),
             Self::NewTopLevelPackageMustEnableStructuredAttrs(inner) => inner.fmt(f),
             Self::TopLevelPackageDisabledStructuredAttrs(inner) => inner.fmt(f),
+            Self::NewByNamePackageNameMismatch(inner) => inner.fmt(f),
+            Self::ByNamePackageNameDiverged(inner) => inner.fmt(f),
             Self::NixFileContainsUselessEscape(inner) => inner.fmt(f),
+            Self::NixFileContainsInvalidSuppression(inner) => inner.fmt(f),
+            Self::NixFileContainsUnusedSuppression(inner) => inner.fmt(f),
+            Self::NixFileUsesDeprecatedFetcherHash(inner) => inner.fmt(f),
+            Self::NixFileUsesRecDerivation(inner) => inner.fmt(f),
+            Self::NixFileUsesDeprecatedAttribute(inner) => inner.fmt(f),
+            Self::NewByNamePackageUsesAliasArgument(inner) => inner.fmt(f),
+            Self::ByNamePackageStartedUsingAliasArgument(inner) => inner.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_have_matching_documentation() {
+        for check in CHECKS {
+            let code = check.code;
+            assert!(
+                check.explanation.starts_with(&format!("# {code}: ")),
+                "The documentation of {code} should start with its heading"
+            );
         }
+        assert!(CHECKS.is_sorted_by_key(|check| check.code));
+        assert_eq!(
+            check("npv-142").map(Check::title),
+            Some("Package is in the wrong by-name shard")
+        );
+        assert!(check("NPV-999").is_none());
     }
 }
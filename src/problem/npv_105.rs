@@ -0,0 +1,33 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+
+use crate::location::Location;
+use crate::structure;
+
+#[derive(Clone, Debug, new)]
+pub struct ByNameOverrideContainsEmptyArgument {
+    #[new(into)]
+    package_name: String,
+    location: Location,
+}
+
+impl fmt::Display for ByNameOverrideContainsEmptyArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            package_name,
+            location,
+        } = self;
+        let relative_package_file = structure::relative_file_for_package(package_name);
+        writedoc!(
+            f,
+            "
+            - Because {relative_package_file} exists, the attribute `{package_name}` is already defined automatically.
+              The manual definition `callPackage ... {{ }}` in {} on line {} is redundant, since it doesn't pass any arguments. Please remove it.
+            ",
+            location.file,
+            location.line,
+        )
+    }
+}
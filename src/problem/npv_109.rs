@@ -1,18 +1,19 @@
 use std::fmt;
 
 use derive_new::new;
-
-use crate::structure;
+use relative_path::RelativePathBuf;
 
 #[derive(Clone, Debug, new)]
 pub struct ByNameShardIsNotDirectory {
     #[new(into)]
-    shard_name: String,
+    relative_shard_path: RelativePathBuf,
 }
 
 impl fmt::Display for ByNameShardIsNotDirectory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let relative_shard_path = structure::relative_dir_for_shard(&self.shard_name);
+        let Self {
+            relative_shard_path,
+        } = self;
         write!(
             f,
             "- {relative_shard_path}: This is a file, but it should be a directory.",
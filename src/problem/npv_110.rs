@@ -2,18 +2,22 @@ use std::fmt;
 
 use derive_new::new;
 
-use crate::structure;
+use relative_path::RelativePathBuf;
 
 #[derive(Clone, Debug, new)]
 pub struct ByNameShardIsInvalid {
     #[new(into)]
     shard_name: String,
+    #[new(into)]
+    relative_shard_path: RelativePathBuf,
 }
 
 impl fmt::Display for ByNameShardIsInvalid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let shard_name = &self.shard_name;
-        let relative_shard_path = structure::relative_dir_for_shard(shard_name);
+        let Self {
+            shard_name,
+            relative_shard_path,
+        } = self;
         write!(
             f,
             "- {relative_shard_path}: Invalid directory name \"{shard_name}\", must be at most 2 ASCII characters, starting with a-z or \"_\", consisting of a-z, 0-9, \"-\" or \"_\".",
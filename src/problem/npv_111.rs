@@ -2,20 +2,19 @@ use std::ffi::OsString;
 use std::fmt;
 
 use derive_new::new;
-
-use crate::structure;
+use relative_path::RelativePathBuf;
 
 #[derive(Clone, Debug, new)]
 pub struct ByNameShardIsCaseSensitiveDuplicate {
     #[new(into)]
-    shard_name: String,
+    relative_shard_path: RelativePathBuf,
     first: OsString,
     second: OsString,
 }
 
 impl fmt::Display for ByNameShardIsCaseSensitiveDuplicate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let relative_shard_path = structure::relative_dir_for_shard(&self.shard_name);
+        let relative_shard_path = &self.relative_shard_path;
         let first = self.first.to_string_lossy();
         let second = self.second.to_string_lossy();
         write!(
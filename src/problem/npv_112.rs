@@ -0,0 +1,25 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct ByNameRequiredFileIsMissingOrEmpty {
+    #[new(into)]
+    relative_file_path: RelativePathBuf,
+    exists: bool,
+}
+
+impl fmt::Display for ByNameRequiredFileIsMissingOrEmpty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_file_path,
+            exists,
+        } = self;
+        let state = if *exists { "is empty" } else { "doesn't exist" };
+        write!(
+            f,
+            "- {relative_file_path}: This file is required, but it {state}.",
+        )
+    }
+}
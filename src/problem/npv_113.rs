@@ -0,0 +1,20 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct ByNameContainsHiddenFile {
+    #[new(into)]
+    relative_path: RelativePathBuf,
+}
+
+impl fmt::Display for ByNameContainsHiddenFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { relative_path } = self;
+        write!(
+            f,
+            "- {relative_path}: This is a hidden or temporary file, which was probably added by accident. Please remove it.",
+        )
+    }
+}
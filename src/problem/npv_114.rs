@@ -0,0 +1,22 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct ByNamePackageNamesCollide {
+    #[new(into)]
+    first: RelativePathBuf,
+    #[new(into)]
+    second: RelativePathBuf,
+}
+
+impl fmt::Display for ByNamePackageNamesCollide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { first, second } = self;
+        write!(
+            f,
+            "- {first}: The package name only differs from {second} in `-` and `_`, which is easy to confuse."
+        )
+    }
+}
@@ -0,0 +1,25 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct PackageContainsUnusedNixFile {
+    #[new(into)]
+    relative_package_dir: RelativePathBuf,
+    #[new(into)]
+    subpath: RelativePathBuf,
+}
+
+impl fmt::Display for PackageContainsUnusedNixFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_package_dir,
+            subpath,
+        } = self;
+        write!(
+            f,
+            "- {relative_package_dir}: Nix file {subpath} is not referenced from package.nix or any file it uses.",
+        )
+    }
+}
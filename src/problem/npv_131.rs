@@ -0,0 +1,39 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug)]
+pub enum Reason {
+    TooLarge { size: u64, max_size: u64 },
+    Binary(&'static str),
+}
+
+#[derive(Clone, Debug, new)]
+pub struct PackageContainsLargeOrBinaryFile {
+    #[new(into)]
+    relative_package_dir: RelativePathBuf,
+    #[new(into)]
+    subpath: RelativePathBuf,
+    reason: Reason,
+}
+
+impl fmt::Display for PackageContainsLargeOrBinaryFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_package_dir,
+            subpath,
+            reason,
+        } = self;
+        match reason {
+            Reason::TooLarge { size, max_size } => write!(
+                f,
+                "- {relative_package_dir}: File {subpath} has a size of {size} bytes, which exceeds the maximum of {max_size} bytes.",
+            ),
+            Reason::Binary(format) => write!(
+                f,
+                "- {relative_package_dir}: File {subpath} looks like a binary file ({format}).",
+            ),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Malformation {
+    Empty,
+    InvalidHunkHeader {
+        line: usize,
+    },
+    HunkLengthMismatch {
+        line: usize,
+        expected_old: u64,
+        expected_new: u64,
+        actual_old: u64,
+        actual_new: u64,
+    },
+}
+
+#[derive(Clone, Debug, new)]
+pub struct PatchFileIsMalformed {
+    #[new(into)]
+    relative_package_dir: RelativePathBuf,
+    #[new(into)]
+    subpath: RelativePathBuf,
+    malformation: Malformation,
+}
+
+impl fmt::Display for PatchFileIsMalformed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_package_dir,
+            subpath,
+            malformation,
+        } = self;
+        match malformation {
+            Malformation::Empty => write!(
+                f,
+                "- {relative_package_dir}: Patch file {subpath} doesn't contain any changes.",
+            ),
+            Malformation::InvalidHunkHeader { line } => write!(
+                f,
+                "- {relative_package_dir}: Patch file {subpath} has an invalid hunk header at line {line}.",
+            ),
+            Malformation::HunkLengthMismatch {
+                line,
+                expected_old,
+                expected_new,
+                actual_old,
+                actual_new,
+            } => write!(
+                f,
+                "- {relative_package_dir}: Patch file {subpath} has a hunk at line {line} declaring {expected_old} old and {expected_new} new lines, but it contains {actual_old} old and {actual_new} new lines.",
+            ),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NonPortability {
+    StorePath { line: usize },
+    MixedLineEndings { crlf: usize, lf: usize },
+}
+
+#[derive(Clone, Debug, new)]
+pub struct PatchFileIsNonPortable {
+    #[new(into)]
+    relative_package_dir: RelativePathBuf,
+    #[new(into)]
+    subpath: RelativePathBuf,
+    non_portability: NonPortability,
+}
+
+impl fmt::Display for PatchFileIsNonPortable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_package_dir,
+            subpath,
+            non_portability,
+        } = self;
+        match non_portability {
+            NonPortability::StorePath { line } => write!(
+                f,
+                "- {relative_package_dir}: Patch file {subpath} contains an absolute /nix/store path at line {line}.",
+            ),
+            NonPortability::MixedLineEndings { crlf, lf } => write!(
+                f,
+                "- {relative_package_dir}: Patch file {subpath} mixes line endings: {crlf} lines end in CRLF and {lf} in LF.",
+            ),
+        }
+    }
+}
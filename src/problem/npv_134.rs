@@ -0,0 +1,30 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct PackageFunctionArgumentIsUnused {
+    #[new(into)]
+    relative_package_dir: RelativePathBuf,
+    #[new(into)]
+    file: RelativePathBuf,
+    line: usize,
+    #[new(into)]
+    argument: String,
+}
+
+impl fmt::Display for PackageFunctionArgumentIsUnused {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            relative_package_dir,
+            file,
+            line,
+            argument,
+        } = self;
+        write!(
+            f,
+            "- {relative_package_dir}: File {file} at line {line} declares the function argument `{argument}`, which is never used.",
+        )
+    }
+}
@@ -1,19 +1,19 @@
 use std::fmt;
 
 use derive_new::new;
-
-use crate::structure;
+use relative_path::RelativePathBuf;
 
 #[derive(Clone, Debug, new)]
 pub struct PackageDirectoryIsNotDirectory {
     #[new(into)]
-    package_name: String,
+    relative_package_dir: RelativePathBuf,
 }
 
 impl fmt::Display for PackageDirectoryIsNotDirectory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Self { package_name } = self;
-        let relative_package_dir = structure::relative_dir_for_package(package_name);
+        let Self {
+            relative_package_dir,
+        } = self;
         write!(
             f,
             "- {relative_package_dir}: This path is a file, but it should be a directory.",
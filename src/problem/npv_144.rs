@@ -1,19 +1,21 @@
 use std::fmt;
 
 use derive_new::new;
+use relative_path::RelativePathBuf;
 
-use crate::structure::{self, PACKAGE_NIX_FILENAME};
+use crate::structure::PACKAGE_NIX_FILENAME;
 
 #[derive(Clone, Debug, new)]
 pub struct PackageNixIsNotFile {
     #[new(into)]
-    package_name: String,
+    relative_package_dir: RelativePathBuf,
 }
 
 impl fmt::Display for PackageNixIsNotFile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let Self { package_name } = self;
-        let relative_package_dir = structure::relative_dir_for_package(package_name);
+        let Self {
+            relative_package_dir,
+        } = self;
         write!(
             f,
             "- {relative_package_dir}: \"{PACKAGE_NIX_FILENAME}\" must be a file.",
@@ -0,0 +1,35 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+/// A syntax error reported by rnix, with its location converted to a line and column.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileHasSyntaxErrors {
+    #[new(into)]
+    file: RelativePathBuf,
+    errors: Vec<SyntaxError>,
+}
+
+impl fmt::Display for NixFileHasSyntaxErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { file, errors } = self;
+        write!(f, "- {file}: File can't be parsed:")?;
+        for SyntaxError {
+            line,
+            column,
+            message,
+        } in errors
+        {
+            write!(f, "\n  - Line {line}, column {column}: {message}")?;
+        }
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ use std::fmt;
 
 use derive_new::new;
 use indoc::writedoc;
+use itertools::Itertools;
 use relative_path::RelativePathBuf;
 
 use crate::structure;
@@ -13,6 +14,8 @@ pub struct TopLevelPackageMovedOutOfByName {
     #[new(into)]
     call_package_path: Option<RelativePathBuf>,
     #[new(into)]
+    overrides: Option<Vec<String>>,
+    #[new(into)]
     file: RelativePathBuf,
 }
 
@@ -21,6 +24,7 @@ impl fmt::Display for TopLevelPackageMovedOutOfByName {
         let Self {
             package_name,
             call_package_path,
+            overrides,
             file,
         } = self;
         let relative_package_file = structure::relative_file_for_package(package_name);
@@ -31,8 +35,22 @@ impl fmt::Display for TopLevelPackageMovedOutOfByName {
             f,
             "
             - Attribute `{package_name}` was previously defined in {relative_package_file}, but is now manually defined as `callPackage {call_package_arg} {{ /* ... */ }}` in {file}.
-              Please move the package back and remove the manual `callPackage`.
             ",
-        )
+        )?;
+        match overrides.as_deref() {
+            Some([]) => writeln!(
+                f,
+                "  Please move the package back and remove the manual `callPackage`."
+            ),
+            Some(overrides) => writeln!(
+                f,
+                "  Please move the package back. Since the manual `callPackage` overrides {}, change it to call {relative_package_file} instead of removing it.",
+                overrides.iter().map(|name| format!("`{name}`")).join(", ")
+            ),
+            None => writeln!(
+                f,
+                "  Please move the package back. Since the second `callPackage` argument isn't an attribute set literal, it couldn't be determined what it overrides, so check by hand whether the manual `callPackage` needs to be kept calling {relative_package_file}."
+            ),
+        }
     }
 }
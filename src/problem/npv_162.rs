@@ -2,6 +2,7 @@ use std::fmt;
 
 use derive_new::new;
 use indoc::writedoc;
+use itertools::Itertools;
 use relative_path::RelativePathBuf;
 
 use crate::structure;
@@ -12,6 +13,10 @@ pub struct NewTopLevelPackageShouldBeByName {
     package_name: String,
     #[new(into)]
     call_package_path: Option<RelativePathBuf>,
+    #[new(into)]
+    overrides: Option<Vec<String>>,
+    #[new(into)]
+    file: RelativePathBuf,
 }
 
 impl fmt::Display for NewTopLevelPackageShouldBeByName {
@@ -19,6 +24,8 @@ impl fmt::Display for NewTopLevelPackageShouldBeByName {
         let Self {
             package_name,
             call_package_path,
+            overrides,
+            file,
         } = self;
         let relative_package_file = structure::relative_file_for_package(package_name);
         let call_package_arg = call_package_path
@@ -29,8 +36,20 @@ impl fmt::Display for NewTopLevelPackageShouldBeByName {
             "
             - Attribute `{package_name}` is a new top-level package using `callPackage {call_package_arg} {{ /* ... */ }}`.
               Please define it in {relative_package_file} instead.
-              See `pkgs/by-name/README.md` for more details.
             ",
-        )
+        )?;
+        match overrides.as_deref() {
+            Some([]) => writeln!(
+                f,
+                "  Since the second `callPackage` argument is empty, the definition in {file} can then simply be removed."
+            )?,
+            Some(overrides) => writeln!(
+                f,
+                "  Since the second `callPackage` argument overrides {}, keep a definition in {file} that calls {relative_package_file} with these.",
+                overrides.iter().map(|name| format!("`{name}`")).join(", ")
+            )?,
+            None => {}
+        }
+        writeln!(f, "  See `pkgs/by-name/README.md` for more details.")
     }
 }
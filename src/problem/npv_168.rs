@@ -0,0 +1,32 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct NewByNamePackageNameMismatch {
+    #[new(into)]
+    package_name: String,
+    #[new(into)]
+    pname: String,
+    #[new(into)]
+    file: RelativePathBuf,
+}
+
+impl fmt::Display for NewByNamePackageNameMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            package_name,
+            pname,
+            file,
+        } = self;
+        writedoc!(
+            f,
+            "
+            - Attribute `{package_name}` is a new package with `pname = \"{pname}\"`, which doesn't match the attribute name.
+              Please change the `pname` in {file} or rename the package directory, such that they only differ in case, `-`, `_` or `.`.
+            ",
+        )
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct ByNamePackageNameDiverged {
+    #[new(into)]
+    package_name: String,
+    #[new(into)]
+    pname: String,
+    #[new(into)]
+    file: RelativePathBuf,
+}
+
+impl fmt::Display for ByNamePackageNameDiverged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            package_name,
+            pname,
+            file,
+        } = self;
+        writedoc!(
+            f,
+            "
+            - Attribute `{package_name}` previously had a `pname` matching the attribute name, but now it has `pname = \"{pname}\"`.
+              Please change the `pname` in {file} back, or rename the package directory to match it.
+            ",
+        )
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+
+use crate::location::Location;
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileContainsInvalidSuppression {
+    location: Location,
+    #[new(into)]
+    text: String,
+    #[new(into)]
+    reason: String,
+}
+
+impl fmt::Display for NixFileContainsInvalidSuppression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            location,
+            text,
+            reason,
+        } = self;
+        writedoc!(
+            f,
+            "
+            - {}: line {}, column {} contains the invalid suppression comment \"{text}\".
+              {reason}. Suppression comments need to be of the form `# nixpkgs-vet: allow NPV-XXX -- <reason>`.
+            ",
+            location.file,
+            location.line,
+            location.column,
+        )
+    }
+}
@@ -0,0 +1,29 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+
+use crate::location::Location;
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileContainsUnusedSuppression {
+    location: Location,
+    codes: Vec<String>,
+}
+
+impl fmt::Display for NixFileContainsUnusedSuppression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { location, codes } = self;
+        writedoc!(
+            f,
+            "
+            - {}: line {}, column {} contains a suppression comment for {}, but no such problem is reported there.
+              Please remove the comment.
+            ",
+            location.file,
+            location.line,
+            location.column,
+            codes.join(", "),
+        )
+    }
+}
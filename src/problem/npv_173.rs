@@ -0,0 +1,58 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+/// A hash passed to a fetcher that should be an SRI `hash` instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetcherHash {
+    pub line: usize,
+    pub fetcher: String,
+    pub kind: FetcherHashKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FetcherHashKind {
+    /// A hash attribute other than `hash`, like `sha256`.
+    DeprecatedAttribute(String),
+    /// A placeholder like `lib.fakeHash` or a hash consisting of zeroes.
+    Placeholder(String),
+}
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileUsesDeprecatedFetcherHash {
+    #[new(into)]
+    file: RelativePathBuf,
+    hashes: Vec<FetcherHash>,
+}
+
+impl fmt::Display for NixFileUsesDeprecatedFetcherHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { file, hashes } = self;
+        writeln!(
+            f,
+            "- {file}: Fetchers must be passed the real hash of their output as an SRI `hash` attribute, but:"
+        )?;
+        for FetcherHash {
+            line,
+            fetcher,
+            kind,
+        } in hashes
+        {
+            match kind {
+                FetcherHashKind::DeprecatedAttribute(attribute) => writeln!(
+                    f,
+                    "  - Line {line}: `{fetcher}` is passed the deprecated `{attribute}` attribute."
+                )?,
+                FetcherHashKind::Placeholder(placeholder) => writeln!(
+                    f,
+                    "  - Line {line}: `{fetcher}` is passed the placeholder hash `{placeholder}`."
+                )?,
+            }
+        }
+        write!(
+            f,
+            "  Please use `hash = \"sha256-...\"` with the real hash, which Nix reports when building with `hash = \"\"` once."
+        )
+    }
+}
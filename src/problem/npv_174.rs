@@ -0,0 +1,41 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+/// A call creating a derivation from a `rec` attribute set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecDerivation {
+    pub line: usize,
+    pub function: String,
+}
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileUsesRecDerivation {
+    #[new(into)]
+    file: RelativePathBuf,
+    calls: Vec<RecDerivation>,
+}
+
+impl fmt::Display for NixFileUsesRecDerivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { file, calls } = self;
+        writeln!(
+            f,
+            "- {file}: Derivations must be created with the `finalAttrs:` pattern, but:"
+        )?;
+        for RecDerivation { line, function } in calls {
+            writeln!(
+                f,
+                "  - Line {line}: `{function}` is passed a `rec` attribute set."
+            )?;
+        }
+        write!(
+            f,
+            "  Please use `{} (finalAttrs: {{ ... }})` instead, referring to other attributes with `finalAttrs.<name>`.",
+            calls
+                .first()
+                .map_or("stdenv.mkDerivation", |call| &call.function),
+        )
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+
+use derive_new::new;
+use relative_path::RelativePathBuf;
+
+/// A use of an attribute that's deprecated or removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeprecatedAttributeUse {
+    pub line: usize,
+    pub expression: String,
+    /// The deprecated attribute path within the expression.
+    pub attribute: String,
+    pub advice: String,
+}
+
+#[derive(Clone, Debug, new)]
+pub struct NixFileUsesDeprecatedAttribute {
+    #[new(into)]
+    file: RelativePathBuf,
+    uses: Vec<DeprecatedAttributeUse>,
+}
+
+impl fmt::Display for NixFileUsesDeprecatedAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self { file, uses } = self;
+        write!(
+            f,
+            "- {file}: Deprecated or removed attributes must not be used, but:"
+        )?;
+        for DeprecatedAttributeUse {
+            line,
+            expression,
+            attribute,
+            advice,
+        } in uses
+        {
+            if expression == attribute {
+                write!(
+                    f,
+                    "\n  - Line {line}: `{expression}` is deprecated. {advice}"
+                )?;
+            } else {
+                write!(
+                    f,
+                    "\n  - Line {line}: `{expression}` uses the deprecated `{attribute}`. {advice}"
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt;
+
+use derive_new::new;
+use indoc::writedoc;
+use relative_path::RelativePathBuf;
+
+#[derive(Clone, Debug, new)]
+pub struct NewByNamePackageUsesAliasArgument {
+    #[new(into)]
+    package_name: String,
+    arguments: Vec<String>,
+    #[new(into)]
+    file: RelativePathBuf,
+}
+
+impl fmt::Display for NewByNamePackageUsesAliasArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            package_name,
+            arguments,
+            file,
+        } = self;
+        let arguments = arguments
+            .iter()
+            .map(|argument| format!("`{argument}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writedoc!(
+            f,
+            "
+            - Attribute `{package_name}` is a new package taking arguments that are only defined as aliases: {arguments}.
+              Please change {file} to take the attributes the aliases refer to instead.
+            ",
+        )
+    }
+}
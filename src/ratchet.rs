@@ -9,8 +9,15 @@ use std::collections::BTreeMap;
 
 use relative_path::RelativePathBuf;
 
+use crate::config::Ratchets;
 use crate::nix_file::CallPackageArgumentInfo;
-use crate::problem::{Problem, npv_160, npv_162, npv_164, npv_165, npv_166, npv_167};
+use crate::problem::npv_173::FetcherHash;
+use crate::problem::npv_174::RecDerivation;
+use crate::problem::npv_175::DeprecatedAttributeUse;
+use crate::problem::{
+    Problem, npv_160, npv_162, npv_164, npv_165, npv_166, npv_167, npv_168, npv_169, npv_173,
+    npv_174, npv_175, npv_176, npv_177,
+};
 use crate::validation::{self, Validation, Validation::Success};
 
 /// The ratchet value for the entirety of Nixpkgs.
@@ -22,23 +29,29 @@ pub struct Nixpkgs {
 }
 
 impl Nixpkgs {
-    /// Validates the ratchet checks for Nixpkgs
-    pub fn compare(from: &Self, to: Self) -> Validation<()> {
+    /// Validates the ratchet checks for Nixpkgs, skipping the ones that aren't enabled
+    pub fn compare(from: &Self, to: Self, ratchets: &Ratchets) -> Validation<()> {
         validation::sequence_(
             // We only loop over the current attributes,
             // we don't need to check ones that were removed
-            to.packages
-                .into_iter()
-                .map(|(name, pkg)| Package::compare(&name, from.packages.get(&name), &pkg)),
+            to.packages.into_iter().map(|(name, pkg)| {
+                Package::compare(&name, from.packages.get(&name), &pkg, ratchets)
+            }),
         )
         .and_(validation::sequence_(to.files.into_iter().map(
-            |(name, file)| File::compare(&name, from.files.get(&name), &file),
+            |(name, file)| File::compare(&name, from.files.get(&name), &file, ratchets),
         )))
     }
 }
 
 /// The ratchet value for a top-level package
 pub struct Package {
+    /// The file defining the package, if known. Only used for `nixpkgs-vet stats`.
+    pub file: Option<RelativePathBuf>,
+
+    /// The maintainers of the package. Only used for `nixpkgs-vet stats`.
+    pub maintainers: Vec<String>,
+
     /// The ratchet value for the check for new packages using pkgs/by-name
     pub uses_by_name: RatchetState<UsesByName>,
 
@@ -47,41 +60,121 @@ pub struct Package {
 
     /// The ratchet value for the check for enabling `__structuredAttrs`.
     pub structured_attrs: RatchetState<StructuredAttrs>,
+
+    /// The ratchet value for the check that the `pname` matches the attribute name.
+    pub package_name: RatchetState<PackageName>,
+
+    /// The ratchet value for the check that `package.nix` doesn't take aliases as arguments.
+    pub alias_arguments: RatchetState<AliasArguments>,
 }
 
 impl Package {
     /// Validates the ratchet checks for a top-level package
-    pub fn compare(name: &str, optional_from: Option<&Self>, to: &Self) -> Validation<()> {
+    pub fn compare(
+        name: &str,
+        optional_from: Option<&Self>,
+        to: &Self,
+        ratchets: &Ratchets,
+    ) -> Validation<()> {
         validation::sequence_([
-            RatchetState::<UsesByName>::compare(
-                name,
-                optional_from.map(|x| &x.uses_by_name),
-                &to.uses_by_name,
-            ),
-            RatchetState::<StrictDeps>::compare(
-                name,
-                optional_from.map(|x| &x.strict_deps),
-                &to.strict_deps,
-            ),
-            RatchetState::<StructuredAttrs>::compare(
-                name,
-                optional_from.map(|x| &x.structured_attrs),
-                &to.structured_attrs,
-            ),
+            if ratchets.uses_by_name {
+                RatchetState::<UsesByName>::compare(
+                    name,
+                    optional_from.map(|x| &x.uses_by_name),
+                    &to.uses_by_name,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.strict_deps {
+                RatchetState::<StrictDeps>::compare(
+                    name,
+                    optional_from.map(|x| &x.strict_deps),
+                    &to.strict_deps,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.structured_attrs {
+                RatchetState::<StructuredAttrs>::compare(
+                    name,
+                    optional_from.map(|x| &x.structured_attrs),
+                    &to.structured_attrs,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.package_name {
+                RatchetState::<PackageName>::compare(
+                    name,
+                    optional_from.map(|x| &x.package_name),
+                    &to.package_name,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.alias_arguments {
+                RatchetState::<AliasArguments>::compare(
+                    name,
+                    optional_from.map(|x| &x.alias_arguments),
+                    &to.alias_arguments,
+                )
+            } else {
+                Success(())
+            },
         ])
     }
 }
 
-pub struct File {}
+/// The ratchet value for a Nix file
+pub struct File {
+    /// The ratchet value for the check that fetchers are passed real SRI hashes.
+    pub fetcher_hashes: RatchetState<FetcherHashes>,
+
+    /// The ratchet value for the check that derivations use `finalAttrs:` instead of `rec`.
+    pub final_attrs: RatchetState<FinalAttrs>,
+
+    /// The ratchet value for the check that no deprecated attributes are used.
+    pub deprecated_attributes: RatchetState<DeprecatedAttributes>,
+}
 
 impl File {
-    /// Validates the ratchet checks for a top-level package
+    /// Validates the ratchet checks for a Nix file
     pub fn compare(
-        _name: &RelativePath,
-        _optional_from: Option<&Self>,
-        _to: &Self,
+        name: &RelativePath,
+        optional_from: Option<&Self>,
+        to: &Self,
+        ratchets: &Ratchets,
     ) -> Validation<()> {
-        Success(())
+        validation::sequence_([
+            if ratchets.fetcher_hashes {
+                RatchetState::<FetcherHashes>::compare(
+                    name.as_str(),
+                    optional_from.map(|x| &x.fetcher_hashes),
+                    &to.fetcher_hashes,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.final_attrs {
+                RatchetState::<FinalAttrs>::compare(
+                    name.as_str(),
+                    optional_from.map(|x| &x.final_attrs),
+                    &to.final_attrs,
+                )
+            } else {
+                Success(())
+            },
+            if ratchets.deprecated_attributes {
+                RatchetState::<DeprecatedAttributes>::compare(
+                    name.as_str(),
+                    optional_from.map(|x| &x.deprecated_attributes),
+                    &to.deprecated_attributes,
+                )
+            } else {
+                Success(())
+            },
+        ])
     }
 }
 
@@ -149,10 +242,21 @@ impl ToProblem for UsesByName {
     fn to_problem(name: &str, optional_from: Option<()>, (to, file): &Self::ToContext) -> Problem {
         let is_new = optional_from.is_none();
         if is_new {
-            npv_162::NewTopLevelPackageShouldBeByName::new(name, to.relative_path.clone()).into()
+            npv_162::NewTopLevelPackageShouldBeByName::new(
+                name,
+                to.relative_path.clone(),
+                to.overrides.clone(),
+                file,
+            )
+            .into()
         } else {
-            npv_160::TopLevelPackageMovedOutOfByName::new(name, to.relative_path.clone(), file)
-                .into()
+            npv_160::TopLevelPackageMovedOutOfByName::new(
+                name,
+                to.relative_path.clone(),
+                to.overrides.clone(),
+                file,
+            )
+            .into()
         }
     }
 }
@@ -214,3 +318,97 @@ impl EnabledAttributeProblem for StructuredAttrsProblem {
         npv_167::TopLevelPackageDisabledStructuredAttrs::new(name, file).into()
     }
 }
+
+/// The ratchet value of a `pkgs/by-name` attribute for its `pname` matching the attribute name.
+///
+/// New packages in `pkgs/by-name` must have a `pname` that only differs from the attribute name in
+/// case and the separators `-`, `_` and `.`. Once it matches, it must not diverge again.
+pub enum PackageName {}
+
+impl ToProblem for PackageName {
+    /// The `pname` and the file defining the package.
+    type ToContext = (String, RelativePathBuf);
+
+    fn to_problem(
+        name: &str,
+        optional_from: Option<()>,
+        (pname, file): &Self::ToContext,
+    ) -> Problem {
+        if optional_from.is_some() {
+            npv_169::ByNamePackageNameDiverged::new(name, pname, file).into()
+        } else {
+            npv_168::NewByNamePackageNameMismatch::new(name, pname, file).into()
+        }
+    }
+}
+
+/// The ratchet value of a `pkgs/by-name` attribute for the arguments of its `package.nix` not being
+/// aliases.
+///
+/// New packages in `pkgs/by-name` must not take arguments that are only defined as aliases, since
+/// those aren't available with `allowAliases = false`. Once a package doesn't, it must not start
+/// doing so.
+pub enum AliasArguments {}
+
+impl ToProblem for AliasArguments {
+    /// The arguments that are aliases and the file defining the package.
+    type ToContext = (Vec<String>, RelativePathBuf);
+
+    fn to_problem(
+        name: &str,
+        optional_from: Option<()>,
+        (arguments, file): &Self::ToContext,
+    ) -> Problem {
+        if optional_from.is_some() {
+            npv_177::ByNamePackageStartedUsingAliasArgument::new(name, arguments.clone(), file)
+                .into()
+        } else {
+            npv_176::NewByNamePackageUsesAliasArgument::new(name, arguments.clone(), file).into()
+        }
+    }
+}
+
+/// The ratchet value of a Nix file for fetchers being passed real SRI hashes.
+///
+/// New Nix files must not pass deprecated hash attributes like `sha256` or placeholder hashes like
+/// `lib.fakeHash` to fetchers. Once a Nix file doesn't, it must not start doing so.
+pub enum FetcherHashes {}
+
+impl ToProblem for FetcherHashes {
+    /// All hashes that should be SRI hashes instead.
+    type ToContext = Vec<FetcherHash>;
+
+    fn to_problem(name: &str, _optional_from: Option<()>, hashes: &Self::ToContext) -> Problem {
+        npv_173::NixFileUsesDeprecatedFetcherHash::new(name, hashes.clone()).into()
+    }
+}
+
+/// The ratchet value of a Nix file for creating derivations with `finalAttrs:` instead of `rec`.
+///
+/// New Nix files must not pass `rec` attribute sets to functions like `stdenv.mkDerivation`. Once a
+/// Nix file doesn't, it must not start doing so.
+pub enum FinalAttrs {}
+
+impl ToProblem for FinalAttrs {
+    /// All calls passing a `rec` attribute set.
+    type ToContext = Vec<RecDerivation>;
+
+    fn to_problem(name: &str, _optional_from: Option<()>, calls: &Self::ToContext) -> Problem {
+        npv_174::NixFileUsesRecDerivation::new(name, calls.clone()).into()
+    }
+}
+
+/// The ratchet value of a Nix file for not using deprecated attributes.
+///
+/// New Nix files must not use attributes from the table of deprecated attributes shipped with
+/// nixpkgs-vet. Once a Nix file doesn't, it must not start doing so.
+pub enum DeprecatedAttributes {}
+
+impl ToProblem for DeprecatedAttributes {
+    /// All uses of deprecated attributes.
+    type ToContext = Vec<DeprecatedAttributeUse>;
+
+    fn to_problem(name: &str, _optional_from: Option<()>, uses: &Self::ToContext) -> Problem {
+        npv_175::NixFileUsesDeprecatedAttribute::new(name, uses.clone()).into()
+    }
+}
@@ -1,20 +1,30 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::ffi::OsStr;
+use std::fs;
+use std::io::Read;
 use std::path::Path;
 
 use anyhow::Context;
-use relative_path::RelativePath;
+use relative_path::{RelativePath, RelativePathBuf};
+use rnix::ast::{self, Expr};
 use rowan::ast::AstNode;
 
 use crate::NixFileStore;
+use crate::config::Config;
 use crate::nix_file::ResolvedPath;
-use crate::problem::{npv_121, npv_122, npv_123, npv_124, npv_125, npv_126, npv_127, npv_128};
-use crate::structure::read_dir_sorted;
+use crate::patch::{self, PATCH_EXTENSIONS};
+use crate::problem::{
+    Problem, npv_121, npv_122, npv_123, npv_124, npv_125, npv_126, npv_127, npv_128, npv_129,
+    npv_130, npv_131, npv_134,
+};
+use crate::structure::{PACKAGE_NIX_FILENAME, is_hidden, read_dir_sorted};
 use crate::validation::{self, ResultIteratorExt, Validation::Success};
 
 /// Check that every package directory in pkgs/by-name doesn't link to outside that directory.
 /// Both symlinks and Nix path expressions are checked.
 pub fn check_references(
     nix_file_store: &mut NixFileStore,
+    config: &Config,
     relative_package_dir: &RelativePath,
     absolute_package_dir: &Path,
 ) -> validation::Result<()> {
@@ -23,18 +33,240 @@ pub fn check_references(
     // We don't use `./.` to keep the error messages cleaner, since there's no canonicalisation
     // going on underneath.
     let subpath = RelativePath::new("");
-    check_path(
+    let context = || {
+        format!(
+            "While checking the references in package directory {}",
+            relative_package_dir
+        )
+    };
+    let result = check_path(
         nix_file_store,
+        config,
         relative_package_dir,
         absolute_package_dir,
         subpath,
     )
-    .with_context(|| {
-        format!(
-            "While checking the references in package directory {}",
-            relative_package_dir
-        )
-    })
+    .with_context(context)?;
+    let reachability_result =
+        check_reachability(nix_file_store, relative_package_dir, absolute_package_dir)
+            .with_context(context)?;
+    let arguments_result =
+        check_unused_arguments(nix_file_store, relative_package_dir, absolute_package_dir)
+            .with_context(context)?;
+    Ok(result.and_(reachability_result).and_(arguments_result))
+}
+
+/// Checks that all arguments of the function in `package.nix` are used.
+fn check_unused_arguments(
+    nix_file_store: &mut NixFileStore,
+    relative_package_dir: &RelativePath,
+    absolute_package_dir: &Path,
+) -> validation::Result<()> {
+    let path = absolute_package_dir.join(PACKAGE_NIX_FILENAME);
+    if !path.is_file() {
+        // NPV-143 or NPV-144 is reported for this already.
+        return Ok(Success(()));
+    }
+    let Some(nix_file) = nix_file_store.get(&path)? else {
+        // NPV-147 is reported for this already.
+        return Ok(Success(()));
+    };
+
+    Ok(validation::sequence_(
+        nix_file.unused_formals().into_iter().map(|formal| {
+            let problem: Problem = npv_134::PackageFunctionArgumentIsUnused::new(
+                relative_package_dir,
+                PACKAGE_NIX_FILENAME,
+                formal.line,
+                formal.name,
+            )
+            .into();
+            if nix_file
+                .suppressions
+                .suppresses(problem.npv_code(), formal.index)
+            {
+                Success(())
+            } else {
+                problem.into()
+            }
+        }),
+    ))
+}
+
+/// The files of a package directory and the static references between them, starting from its
+/// `package.nix`.
+pub struct ReferenceGraph {
+    /// All files in the package directory, relative to it.
+    pub files: BTreeSet<RelativePathBuf>,
+    /// The files referenced by each Nix file that is reachable from `package.nix`. References to
+    /// directories are expanded to all files within them.
+    pub edges: BTreeMap<RelativePathBuf, BTreeSet<RelativePathBuf>>,
+}
+
+impl ReferenceGraph {
+    /// Builds the graph by following path expressions that resolve within the package directory.
+    ///
+    /// Files are followed as Nix files if they end with `.nix`, or if they're the first argument of
+    /// `import` or `callPackage`, in which case a directory means its `default.nix`.
+    ///
+    /// Returns `None` if any of the followed Nix files can't be parsed, since the references of
+    /// those, and with it the graph, aren't known.
+    pub fn new(
+        nix_file_store: &mut NixFileStore,
+        absolute_package_dir: &Path,
+    ) -> anyhow::Result<Option<Self>> {
+        let files = package_files(absolute_package_dir, RelativePath::new(""))?;
+        let mut edges = BTreeMap::new();
+
+        let mut queue = VecDeque::from([RelativePathBuf::from(PACKAGE_NIX_FILENAME)]);
+        while let Some(subpath) = queue.pop_front() {
+            if edges.contains_key(&subpath) || !files.contains(&subpath) {
+                continue;
+            }
+            let Some(nix_file) = nix_file_store.get(&subpath.to_path(absolute_package_dir))? else {
+                return Ok(None);
+            };
+
+            let mut references = BTreeSet::new();
+            for node in nix_file.syntax_root.syntax().descendants() {
+                let Some(ResolvedPath::Within(target)) =
+                    nix_file.static_resolve_path_node(&node, absolute_package_dir)
+                else {
+                    continue;
+                };
+                let imported = is_imported(&node);
+                if target.to_path(absolute_package_dir).is_dir() {
+                    if imported {
+                        queue.push_back(target.join("default.nix"));
+                    }
+                    // `RelativePath::starts_with` compares components, so `""` matches everything.
+                    references.extend(
+                        files
+                            .iter()
+                            .filter(|file| file.starts_with(&target))
+                            .cloned(),
+                    );
+                } else {
+                    if imported {
+                        queue.push_back(target.clone());
+                    }
+                    references.insert(target);
+                }
+            }
+
+            queue.extend(
+                references
+                    .iter()
+                    .filter(|file| file.extension() == Some("nix"))
+                    .cloned(),
+            );
+            edges.insert(subpath, references);
+        }
+
+        Ok(Some(Self { files, edges }))
+    }
+
+    /// Whether a file is `package.nix` or referenced by a file reachable from it.
+    pub fn is_reachable(&self, subpath: &RelativePath) -> bool {
+        subpath == RelativePath::new(PACKAGE_NIX_FILENAME)
+            || self
+                .edges
+                .values()
+                .any(|references| references.iter().any(|file| file == subpath))
+    }
+}
+
+/// Recursively lists the files in a package directory. Symlinks are left out, since path
+/// expressions resolve to their targets, and NPV-125 and NPV-126 check them already.
+fn package_files(
+    absolute_package_dir: &Path,
+    subpath: &RelativePath,
+) -> anyhow::Result<BTreeSet<RelativePathBuf>> {
+    let mut files = BTreeSet::new();
+    for entry in read_dir_sorted(&subpath.to_path(absolute_package_dir))? {
+        let entry_subpath = subpath.join(entry.file_name().to_string_lossy().as_ref());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files.extend(package_files(absolute_package_dir, &entry_subpath)?);
+        } else if !file_type.is_symlink() {
+            files.insert(entry_subpath);
+        }
+    }
+    Ok(files)
+}
+
+/// Whether a path expression is the first argument of `import` or `callPackage`, like
+/// `import ./foo.nix` or `pkgs.callPackage ./foo { }`, which means that it's evaluated as Nix.
+fn is_imported(path: &rnix::SyntaxNode) -> bool {
+    let Some(apply) = path.parent().and_then(ast::Apply::cast) else {
+        return false;
+    };
+    let is_argument = apply
+        .argument()
+        .is_some_and(|argument| argument.syntax() == path);
+    let function_name = match apply.lambda() {
+        Some(Expr::Ident(ident)) => ident.ident_token().map(|token| token.text().to_owned()),
+        Some(Expr::Select(select)) => match select
+            .attrpath()
+            .and_then(|attrpath| attrpath.attrs().last())
+        {
+            Some(ast::Attr::Ident(ident)) => {
+                ident.ident_token().map(|token| token.text().to_owned())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    is_argument && matches!(function_name.as_deref(), Some("import" | "callPackage"))
+}
+
+/// Checks the files reachable from `package.nix` through static references, reporting files
+/// that aren't reachable, and references escaping the directory from files that are only Nix files
+/// because they're imported, since `check_path` only checks files ending with `.nix`.
+fn check_reachability(
+    nix_file_store: &mut NixFileStore,
+    relative_package_dir: &RelativePath,
+    absolute_package_dir: &Path,
+) -> validation::Result<()> {
+    if !absolute_package_dir.join(PACKAGE_NIX_FILENAME).is_file() {
+        // NPV-143 or NPV-144 is reported for this already.
+        return Ok(Success(()));
+    }
+    let Some(graph) = ReferenceGraph::new(nix_file_store, absolute_package_dir)? else {
+        // NPV-147 is reported for the files that can't be parsed already.
+        return Ok(Success(()));
+    };
+
+    let imported_results = graph
+        .edges
+        .keys()
+        .filter(|subpath| subpath.extension() != Some("nix"))
+        .map(|subpath| {
+            check_nix_file(
+                nix_file_store,
+                relative_package_dir,
+                absolute_package_dir,
+                subpath,
+            )
+            .with_context(|| format!("Error while checking imported file {}", subpath))
+        })
+        .collect_vec()?;
+
+    let unused_results = graph
+        .files
+        .iter()
+        // Hidden files like `.gitignore` are typically for tools, not for the package.
+        .filter(|subpath| !is_hidden(subpath.file_name().unwrap_or_default()))
+        .filter(|subpath| !graph.is_reachable(subpath))
+        .map(|subpath| {
+            if subpath.extension() == Some("nix") {
+                npv_129::PackageContainsUnusedNixFile::new(relative_package_dir, subpath).into()
+            } else {
+                npv_130::PackageContainsUnusedFile::new(relative_package_dir, subpath).into()
+            }
+        });
+
+    Ok(validation::sequence_(imported_results).and_(validation::sequence_(unused_results)))
 }
 
 /// Checks for a specific path to not have references outside.
@@ -44,6 +276,7 @@ pub fn check_references(
 /// The absolute package directory gets prepended before doing anything with it though.
 fn check_path(
     nix_file_store: &mut NixFileStore,
+    config: &Config,
     relative_package_dir: &RelativePath,
     absolute_package_dir: &Path,
     subpath: &RelativePath,
@@ -79,6 +312,7 @@ fn check_path(
                 .map(|entry| {
                     check_path(
                         nix_file_store,
+                        config,
                         relative_package_dir,
                         absolute_package_dir,
                         // TODO: The relative_path crate doesn't seem to support OsStr
@@ -89,9 +323,19 @@ fn check_path(
                 .with_context(|| format!("Error while recursing into {}", subpath))?,
         )
     } else if path.is_file() {
-        // Only check Nix files
-        if let Some(ext) = path.extension() {
-            if ext == OsStr::new("nix") {
+        let result =
+            check_file_contents(config, relative_package_dir, absolute_package_dir, subpath)
+                .with_context(|| {
+                    format!("Error while checking the contents of file {}", subpath)
+                })?;
+        // Only check Nix and patch files
+        result.and_(if let Some(ext) = path.extension() {
+            if PATCH_EXTENSIONS
+                .iter()
+                .any(|patch_ext| ext == OsStr::new(patch_ext))
+            {
+                patch::check_patch_file(relative_package_dir, absolute_package_dir, subpath)?
+            } else if ext == OsStr::new("nix") {
                 check_nix_file(
                     nix_file_store,
                     relative_package_dir,
@@ -104,13 +348,68 @@ fn check_path(
             }
         } else {
             Success(())
-        }
+        })
     } else {
         // This should never happen, git doesn't support other file types
         anyhow::bail!("Unsupported file type for path {}", subpath);
     })
 }
 
+/// Magic numbers of binary file formats that shouldn't be vendored into Nixpkgs, with the offset
+/// they're at.
+const BINARY_MAGIC: &[(&str, usize, &[u8])] = &[
+    ("gzip archive", 0, b"\x1f\x8b"),
+    ("bzip2 archive", 0, b"BZh"),
+    ("xz archive", 0, b"\xfd7zXZ\x00"),
+    ("zstd archive", 0, b"\x28\xb5\x2f\xfd"),
+    ("zip archive", 0, b"PK\x03\x04"),
+    ("7z archive", 0, b"7z\xbc\xaf\x27\x1c"),
+    ("tar archive", 257, b"ustar"),
+    ("PNG image", 0, b"\x89PNG"),
+    ("JPEG image", 0, b"\xff\xd8\xff"),
+    ("GIF image", 0, b"GIF8"),
+];
+
+/// Check that a file in a package directory is neither too large nor binary.
+fn check_file_contents(
+    config: &Config,
+    relative_package_dir: &RelativePath,
+    absolute_package_dir: &Path,
+    subpath: &RelativePath,
+) -> validation::Result<()> {
+    let path = subpath.to_path(absolute_package_dir);
+
+    let size = path.metadata()?.len();
+    let max_size = config.by_name.max_file_size;
+    if size > max_size {
+        return Ok(npv_131::PackageContainsLargeOrBinaryFile::new(
+            relative_package_dir,
+            subpath,
+            npv_131::Reason::TooLarge { size, max_size },
+        )
+        .into());
+    }
+
+    // Like Git, only look at the start of the file to decide whether it's binary.
+    let mut start = vec![];
+    fs::File::open(&path)?.take(8000).read_to_end(&mut start)?;
+    let binary_format = BINARY_MAGIC
+        .iter()
+        .find(|(_, offset, magic)| start.get(*offset..).is_some_and(|s| s.starts_with(magic)))
+        .map(|(format, _, _)| *format)
+        .or_else(|| start.contains(&0).then_some("contains NUL bytes"));
+
+    Ok(match binary_format {
+        Some(format) => npv_131::PackageContainsLargeOrBinaryFile::new(
+            relative_package_dir,
+            subpath,
+            npv_131::Reason::Binary(format),
+        )
+        .into(),
+        None => Success(()),
+    })
+}
+
 /// Check whether a Nix file contains path expression references pointing outside the package
 /// directory.
 fn check_nix_file(
@@ -121,19 +420,25 @@ fn check_nix_file(
 ) -> validation::Result<()> {
     let path = subpath.to_path(absolute_package_dir);
 
-    let nix_file = nix_file_store.get(&path)?;
+    let Some(nix_file) = nix_file_store.get(&path)? else {
+        // NPV-147 is reported for this already.
+        return Ok(Success(()));
+    };
 
     Ok(validation::sequence_(
         nix_file.syntax_root.syntax().descendants().map(|node| {
-            let line = nix_file.line_index.line(node.text_range().start().into());
+            let index = node.text_range().start().into();
+            let line = nix_file.line_index.line(index);
             let text = node.text().to_string();
 
-            // We're only interested in Path expressions
-            let Some(path) = rnix::ast::Path::cast(node) else {
+            // We're only interested in path expressions
+            let Some(resolved_path) =
+                nix_file.static_resolve_path_node(&node, absolute_package_dir)
+            else {
                 return Success(());
             };
 
-            match nix_file.static_resolve_path(&path, absolute_package_dir) {
+            let problem: Problem = match resolved_path {
                 ResolvedPath::Interpolated => npv_121::NixFileContainsPathInterpolation::new(
                     relative_package_dir,
                     subpath,
@@ -180,8 +485,14 @@ fn check_nix_file(
                 ResolvedPath::Within(..) => {
                     // No need to handle the case of it being inside the directory, since we scan
                     // through the entire directory recursively in any case.
-                    Success(())
+                    return Success(());
                 }
+            };
+
+            if nix_file.suppressions.suppresses(problem.npv_code(), index) {
+                Success(())
+            } else {
+                problem.into()
             }
         }),
     ))
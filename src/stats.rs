@@ -0,0 +1,201 @@
+//! Progress statistics for the ratchet checks, see `nixpkgs-vet stats`.
+//!
+//! This counts how many packages are in each `RatchetState` for each ratchet, which shows how far
+//! the migrations are from being done.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::ratchet::{self, RatchetState, ToProblem};
+
+/// How to break down the statistics, in addition to the totals.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Breakdown {
+    /// By the file defining each package.
+    File,
+    /// By each maintainer of a package.
+    Maintainer,
+}
+
+/// The number of packages in each ratchet state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub loose: usize,
+    pub tight: usize,
+    pub non_applicable: usize,
+}
+
+impl Counts {
+    fn add<R: ToProblem>(&mut self, state: &RatchetState<R>) {
+        match state {
+            RatchetState::Loose(_) => self.loose += 1,
+            RatchetState::Tight => self.tight += 1,
+            RatchetState::NonApplicable => self.non_applicable += 1,
+        }
+    }
+}
+
+/// The counts for each ratchet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RatchetCounts {
+    pub uses_by_name: Counts,
+    pub strict_deps: Counts,
+    pub structured_attrs: Counts,
+    pub package_name: Counts,
+    pub alias_arguments: Counts,
+}
+
+impl RatchetCounts {
+    fn add(&mut self, package: &ratchet::Package) {
+        self.uses_by_name.add(&package.uses_by_name);
+        self.strict_deps.add(&package.strict_deps);
+        self.structured_attrs.add(&package.structured_attrs);
+        self.package_name.add(&package.package_name);
+        self.alias_arguments.add(&package.alias_arguments);
+    }
+
+    fn rows(&self) -> [(&'static str, Counts); 5] {
+        [
+            ("uses-by-name", self.uses_by_name),
+            ("strict-deps", self.strict_deps),
+            ("structured-attrs", self.structured_attrs),
+            ("package-name", self.package_name),
+            ("alias-arguments", self.alias_arguments),
+        ]
+    }
+}
+
+/// The statistics for a whole Nixpkgs.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub total: RatchetCounts,
+    /// The counts for each file or maintainer, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakdown: Option<BTreeMap<String, RatchetCounts>>,
+}
+
+impl Stats {
+    pub fn new(nixpkgs: &ratchet::Nixpkgs, breakdown: Option<Breakdown>) -> Self {
+        let mut total = RatchetCounts::default();
+        for package in nixpkgs.packages.values() {
+            total.add(package);
+        }
+
+        let breakdown = breakdown.map(|breakdown| {
+            let mut groups = BTreeMap::<String, RatchetCounts>::new();
+            for package in nixpkgs.packages.values() {
+                let keys = match breakdown {
+                    Breakdown::File => vec![
+                        package
+                            .file
+                            .as_ref()
+                            .map_or_else(|| "(unknown)".to_owned(), ToString::to_string),
+                    ],
+                    Breakdown::Maintainer if package.maintainers.is_empty() => {
+                        vec!["(none)".to_owned()]
+                    }
+                    Breakdown::Maintainer => package.maintainers.clone(),
+                };
+                for key in keys {
+                    groups.entry(key).or_default().add(package);
+                }
+            }
+            groups
+        });
+
+        Self { total, breakdown }
+    }
+}
+
+/// A table for humans.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter, ratchet: &str, counts: Counts, group: &str| {
+            let line = format!(
+                "{:<17} {:>7} {:>7} {:>14}  {group}",
+                ratchet, counts.loose, counts.tight, counts.non_applicable
+            );
+            writeln!(f, "{}", line.trim_end())
+        };
+
+        writeln!(
+            f,
+            "{:<17} {:>7} {:>7} {:>14}",
+            "RATCHET", "LOOSE", "TIGHT", "NON-APPLICABLE"
+        )?;
+        for (ratchet, counts) in self.total.rows() {
+            row(f, ratchet, counts, "")?;
+        }
+
+        if let Some(breakdown) = &self.breakdown {
+            for (group, counts) in breakdown {
+                writeln!(f)?;
+                for (ratchet, counts) in counts.rows() {
+                    row(f, ratchet, counts, group)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratchet::RatchetState::{Loose, NonApplicable, Tight};
+    use relative_path::RelativePathBuf;
+
+    fn package(file: &str, maintainers: &[&str], strict_deps: bool) -> ratchet::Package {
+        ratchet::Package {
+            file: Some(RelativePathBuf::from(file)),
+            maintainers: maintainers.iter().map(|m| (*m).to_owned()).collect(),
+            uses_by_name: NonApplicable,
+            strict_deps: if strict_deps {
+                Tight
+            } else {
+                Loose(RelativePathBuf::from(file))
+            },
+            structured_attrs: Tight,
+            package_name: NonApplicable,
+            alias_arguments: NonApplicable,
+        }
+    }
+
+    #[test]
+    fn counts_ratchet_states() {
+        let nixpkgs = ratchet::Nixpkgs {
+            packages: BTreeMap::from([
+                ("foo".to_owned(), package("a.nix", &["alice"], false)),
+                ("bar".to_owned(), package("a.nix", &["alice", "bob"], true)),
+                ("baz".to_owned(), package("b.nix", &[], false)),
+            ]),
+            files: BTreeMap::new(),
+        };
+
+        let stats = Stats::new(&nixpkgs, Some(Breakdown::Maintainer));
+        assert_eq!(
+            stats.total.strict_deps,
+            Counts {
+                loose: 2,
+                tight: 1,
+                non_applicable: 0
+            }
+        );
+        assert_eq!(stats.total.uses_by_name.non_applicable, 3);
+
+        let breakdown = stats.breakdown.expect("breakdown");
+        assert_eq!(
+            breakdown.keys().collect::<Vec<_>>(),
+            ["(none)", "alice", "bob"]
+        );
+        assert_eq!(breakdown["alice"].strict_deps.loose, 1);
+        assert_eq!(breakdown["bob"].strict_deps.tight, 1);
+
+        let stats = Stats::new(&nixpkgs, Some(Breakdown::File));
+        let breakdown = stats.breakdown.expect("breakdown");
+        assert_eq!(breakdown["a.nix"].structured_attrs.tight, 2);
+        assert_eq!(breakdown["b.nix"].strict_deps.loose, 1);
+    }
+}
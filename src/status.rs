@@ -9,8 +9,12 @@ pub enum Status {
     /// It's all green.
     ValidatedSuccessfully,
 
-    /// The base branch is broken, but this PR fixes it. Nice job!
-    BranchHealed,
+    /// All errors are fixed, but there are some problems with a lower severity listed.
+    ValidatedWithWarnings(Vec<Problem>),
+
+    /// The base branch is broken, but this PR fixes it. Nice job! Problems with a lower severity
+    /// are still listed.
+    BranchHealed(Vec<Problem>),
 
     /// The base branch fails, the PR doesn't fix it, and the PR may also introduce additional
     /// problems.
@@ -20,6 +24,9 @@ pub enum Status {
     /// branch would break.
     ProblemsIntroduced(Vec<Problem>),
 
+    /// Without a base to compare against, the problems listed were found.
+    ProblemsFound(Vec<Problem>),
+
     /// This PR introduces additional instances of discouraged patterns. Please fix them before
     /// merging.
     DiscouragedPatternedIntroduced(Vec<Problem>),
@@ -31,9 +38,12 @@ pub enum Status {
 impl Status {
     fn errors(&self) -> Option<&Vec<Problem>> {
         match self {
-            Self::ValidatedSuccessfully | Self::BranchHealed | Self::Error(..) => None,
-            Self::BranchStillBroken(errors)
+            Self::ValidatedSuccessfully | Self::Error(..) => None,
+            Self::ValidatedWithWarnings(errors)
+            | Self::BranchHealed(errors)
+            | Self::BranchStillBroken(errors)
             | Self::ProblemsIntroduced(errors)
+            | Self::ProblemsFound(errors)
             | Self::DiscouragedPatternedIntroduced(errors) => Some(errors),
         }
     }
@@ -42,13 +52,30 @@ impl Status {
         // These all respect the NO_COLOR environment variable even if `use_color` is true.
         let maybe_green = |s: &str| if use_color { s.green() } else { s.into() };
         let maybe_yellow = |s: &str| if use_color { s.yellow() } else { s.into() };
-        // Print each error with its wiki link.
+        // Print each error with a hint to its documentation, which is also available offline.
         if let Some(errors) = self.errors() {
             for error in errors {
-                let url = error.wiki_url();
+                let hint = format!(
+                    "see `nixpkgs-vet explain {}` or {}",
+                    error.npv_code(),
+                    error.wiki_url()
+                );
+                // Problems that aren't errors are marked with their severity.
+                let url = if error.is_error() {
+                    hint
+                } else {
+                    format!("{}: {hint}", error.severity())
+                };
 
                 if use_color {
                     let error_str = format!("{error}");
+                    let highlight = |s: &str| {
+                        if error.is_error() {
+                            s.red()
+                        } else {
+                            s.yellow()
+                        }
+                    };
 
                     // Most errors follow "- {path}: {message}". When we can identify
                     // that pattern, make the path bold and the message red. Otherwise
@@ -57,12 +84,12 @@ impl Status {
                         && let Some((location, message)) = rest.split_once(": ")
                         && !location.contains('\n')
                     {
-                        writeln!(f, "- {}: {} ({})", location.bold(), message.red(), url)?;
+                        writeln!(f, "- {}: {} ({})", location.bold(), highlight(message), url)?;
                         continue;
                     }
 
                     // Fallback for messages that don't match the simple pattern.
-                    writeln!(f, "{} ({})", error_str.red(), url)?;
+                    writeln!(f, "{} ({})", highlight(&error_str), url)?;
                 } else {
                     writeln!(f, "{error} ({url})")?;
                 }
@@ -73,7 +100,10 @@ impl Status {
         let message = match self {
             Self::Error(error) => format!("{} {:#}", maybe_yellow("I/O error: "), error).into(),
             Self::ValidatedSuccessfully => maybe_green("Validated successfully"),
-            Self::BranchHealed => {
+            Self::ValidatedWithWarnings(..) => {
+                maybe_green("Validated successfully, but with the warnings listed above")
+            }
+            Self::BranchHealed(..) => {
                 maybe_green("The base branch is broken, but this PR fixes it. Nice job!")
             }
             Self::BranchStillBroken(..) => maybe_yellow(
@@ -85,6 +115,9 @@ impl Status {
                 "This PR introduces the problems listed above. Please fix them before merging, \
                  otherwise the base branch would break.",
             ),
+            Self::ProblemsFound(..) => {
+                maybe_yellow("Found the problems listed above. Please fix them.")
+            }
             Self::DiscouragedPatternedIntroduced(..) => maybe_yellow(
                 "This PR introduces additional instances of discouraged patterns as listed above. \
                  Please fix them before merging.",
@@ -103,9 +136,12 @@ impl From<anyhow::Error> for Status {
 impl From<Status> for ExitCode {
     fn from(status: Status) -> Self {
         match status {
-            Status::ValidatedSuccessfully | Status::BranchHealed => ExitCode::SUCCESS,
+            Status::ValidatedSuccessfully
+            | Status::ValidatedWithWarnings(..)
+            | Status::BranchHealed(..) => ExitCode::SUCCESS,
             Status::BranchStillBroken(..)
             | Status::ProblemsIntroduced(..)
+            | Status::ProblemsFound(..)
             | Status::DiscouragedPatternedIntroduced(..) => ExitCode::from(1),
             Status::Error(..) => ExitCode::from(2),
         }
@@ -1,16 +1,23 @@
-use std::fs::DirEntry;
+use std::fs::{self, DirEntry};
 use std::path::Path;
 use std::sync::LazyLock;
 
 use anyhow::Context;
 use itertools::{concat, process_results};
 use regex::Regex;
-use relative_path::RelativePathBuf;
+use relative_path::{RelativePath, RelativePathBuf};
 
 use crate::NixFileStore;
-use crate::problem::{npv_109, npv_110, npv_111, npv_140, npv_141, npv_142, npv_143, npv_144};
+use crate::config::Config;
+use crate::problem::{
+    Category, npv_109, npv_110, npv_111, npv_112, npv_113, npv_114, npv_140, npv_141, npv_142,
+    npv_143, npv_144,
+};
 use crate::references;
-use crate::validation::{self, ResultIteratorExt, Validation::Success};
+use crate::validation::{
+    self, ResultIteratorExt,
+    Validation::{Failure, Success},
+};
 
 pub const BASE_SUBPATH: &str = "pkgs/by-name";
 pub const PACKAGE_NIX_FILENAME: &str = "package.nix";
@@ -50,36 +57,86 @@ pub fn relative_file_for_package(package_name: &str) -> RelativePathBuf {
     relative_dir_for_package(package_name).join(PACKAGE_NIX_FILENAME)
 }
 
-/// Check the structure of Nixpkgs, returning the attribute names that are defined in
-/// `pkgs/by-name`
+/// Check the structure of a `pkgs/by-name`-like directory in Nixpkgs, returning the attribute
+/// names that are defined in it.
 pub fn check_structure(
     path: &Path,
+    by_name_dir: &RelativePath,
+    config: &Config,
     nix_file_store: &mut NixFileStore,
 ) -> validation::Result<Vec<String>> {
-    let base_dir = path.join(BASE_SUBPATH);
+    let base_dir = by_name_dir.to_path(path);
+
+    let required_file_results = config
+        .by_name
+        .required_root_files
+        .iter()
+        .map(|file_name| -> validation::Result<()> {
+            let file_path = base_dir.join(file_name);
+            let relative_file_path = by_name_dir.join(file_name);
+            Ok(if !file_path.is_file() {
+                npv_112::ByNameRequiredFileIsMissingOrEmpty::new(relative_file_path, false).into()
+            } else {
+                let contents = fs::read(&file_path)
+                    .with_context(|| format!("Could not read file {}", file_path.display()))?;
+                if contents.iter().all(u8::is_ascii_whitespace) {
+                    npv_112::ByNameRequiredFileIsMissingOrEmpty::new(relative_file_path, true)
+                        .into()
+                } else {
+                    Success(())
+                }
+            })
+        })
+        .collect_vec()?;
+    let required_file_result = config.filter(validation::sequence_(required_file_results));
+
+    // All package directories, to check for collisions across shards afterwards.
+    let mut package_dirs = vec![];
 
     let shard_results = read_dir_sorted(&base_dir)?
         .into_iter()
         .map(|shard_entry| -> validation::Result<_> {
             let shard_path = shard_entry.path();
             let shard_name = shard_entry.file_name().to_string_lossy().into_owned();
+            let relative_shard_path = by_name_dir.join(&shard_name);
 
-            Ok(if shard_name == "README.md" {
-                // README.md is allowed to be a file and not checked
+            Ok(if config.is_root_file(&shard_name) {
+                // Files like README.md are allowed, required ones are checked above. This comes
+                // first, so that hidden files like .gitattributes can be allowed too
                 Success(vec![])
+            } else if is_hidden(&shard_name) {
+                config
+                    .filter(npv_113::ByNameContainsHiddenFile::new(relative_shard_path).into())
+                    .map(|()| vec![])
             } else if !shard_path.is_dir() {
                 // We can't check for any other errors if it's not a directory, since there are no
                 // subdirectories to check.
-                npv_109::ByNameShardIsNotDirectory::new(shard_name).into()
+                Failure(vec![
+                    npv_109::ByNameShardIsNotDirectory::new(relative_shard_path).into(),
+                ])
             } else {
                 let shard_name_valid = SHARD_NAME_REGEX.is_match(&shard_name);
                 let result = if !shard_name_valid {
-                    npv_110::ByNameShardIsInvalid::new(shard_name.clone()).into()
+                    npv_110::ByNameShardIsInvalid::new(shard_name.clone(), &relative_shard_path)
+                        .into()
                 } else {
                     Success(())
                 };
 
-                let entries = read_dir_sorted(&shard_path)?;
+                let (hidden_entries, entries): (Vec<_>, Vec<_>) = read_dir_sorted(&shard_path)?
+                    .into_iter()
+                    .partition(|entry| is_hidden(&entry.file_name().to_string_lossy()));
+
+                package_dirs.extend(entries.iter().filter(|entry| entry.path().is_dir()).map(
+                    |entry| relative_shard_path.join(entry.file_name().to_string_lossy().as_ref()),
+                ));
+
+                let hidden_results = hidden_entries.iter().map(|entry| {
+                    npv_113::ByNameContainsHiddenFile::new(
+                        relative_shard_path.join(entry.file_name().to_string_lossy().as_ref()),
+                    )
+                    .into()
+                });
 
                 let duplicate_results = entries
                     .iter()
@@ -87,21 +144,27 @@ pub fn check_structure(
                     .filter(|(l, r)| l.file_name().eq_ignore_ascii_case(r.file_name()))
                     .map(|(l, r)| {
                         npv_111::ByNameShardIsCaseSensitiveDuplicate::new(
-                            shard_name.clone(),
+                            &relative_shard_path,
                             l.file_name(),
                             r.file_name(),
                         )
                         .into()
                     });
 
-                let result = result.and_(validation::sequence_(duplicate_results));
+                let result = config.filter(
+                    result
+                        .and_(validation::sequence_(duplicate_results))
+                        .and_(validation::sequence_(hidden_results)),
+                );
 
                 let package_results = entries
                     .into_iter()
                     .map(|package_entry| {
                         check_package(
                             nix_file_store,
+                            config,
                             path,
+                            by_name_dir,
                             &shard_name,
                             shard_name_valid,
                             &package_entry,
@@ -114,24 +177,69 @@ pub fn check_structure(
         })
         .collect_vec()?;
 
+    let collision_result = config.filter(validation::sequence_(
+        separator_collisions(&package_dirs)
+            .into_iter()
+            .map(|(first, second)| npv_114::ByNamePackageNamesCollide::new(first, second).into()),
+    ));
+
     // Combine the package names contained within each shard into a longer list.
-    Ok(validation::sequence(shard_results).map(concat))
+    Ok(required_file_result
+        .and_(collision_result)
+        .and_(validation::sequence(shard_results).map(concat)))
+}
+
+/// Returns the pairs of package directories whose names only differ in `-` and `_`, like `foo-bar`
+/// and `foo_bar`, which can be in different shards. Names only differing in case are ignored,
+/// since NPV-111 reports those already.
+fn separator_collisions(
+    package_dirs: &[RelativePathBuf],
+) -> Vec<(&RelativePathBuf, &RelativePathBuf)> {
+    use itertools::Itertools;
+
+    let package_name = |dir: &RelativePathBuf| dir.file_name().unwrap_or_default().to_owned();
+    package_dirs
+        .iter()
+        .filter(|dir| PACKAGE_NAME_REGEX.is_match(&package_name(dir)))
+        .into_group_map_by(|dir| package_name(dir).to_lowercase().replace('_', "-"))
+        .into_values()
+        .flat_map(|dirs| {
+            let mut dirs = dirs
+                .into_iter()
+                .sorted()
+                .unique_by(|dir| package_name(dir).to_lowercase());
+            let first = dirs.next();
+            dirs.map(move |dir| (first.expect("first exists if others do"), dir))
+        })
+        .sorted()
+        .collect()
+}
+
+/// Whether a file name looks like it was created by an operating system or editor, like
+/// `.DS_Store`, `.package.nix.swp`, `package.nix~` or `#package.nix#`.
+pub fn is_hidden(file_name: &str) -> bool {
+    file_name.starts_with('.')
+        || file_name.ends_with('~')
+        || (file_name.len() > 1 && file_name.starts_with('#') && file_name.ends_with('#'))
 }
 
 fn check_package(
     nix_file_store: &mut NixFileStore,
+    config: &Config,
     path: &Path,
+    by_name_dir: &RelativePath,
     shard_name: &str,
     shard_name_valid: bool,
     package_entry: &DirEntry,
 ) -> validation::Result<String> {
     let package_path = package_entry.path();
     let package_name = package_entry.file_name().to_string_lossy().into_owned();
-    let relative_package_dir =
-        RelativePathBuf::from(format!("{BASE_SUBPATH}/{shard_name}/{package_name}"));
+    let relative_package_dir = by_name_dir.join(shard_name).join(&package_name);
 
     Ok(if !package_path.is_dir() {
-        npv_140::PackageDirectoryIsNotDirectory::new(package_name).into()
+        Failure(vec![
+            npv_140::PackageDirectoryIsNotDirectory::new(relative_package_dir).into(),
+        ])
     } else {
         let package_name_valid = PACKAGE_NAME_REGEX.is_match(&package_name);
         let result = if !package_name_valid {
@@ -144,14 +252,16 @@ fn check_package(
             Success(())
         };
 
-        let correct_relative_package_dir = relative_dir_for_package(&package_name);
+        let correct_relative_package_dir = by_name_dir
+            .join(shard_for_package(&package_name))
+            .join(&package_name);
         let result = result.and_(if relative_package_dir != correct_relative_package_dir {
             // Only show this error if we have a valid shard and package name.
             // If one of those is wrong, you should fix that first.
             if shard_name_valid && package_name_valid {
                 npv_142::PackageInWrongShard::new(
-                    package_name.clone(),
                     relative_package_dir.clone(),
+                    correct_relative_package_dir,
                 )
                 .into()
             } else {
@@ -163,19 +273,24 @@ fn check_package(
 
         let package_nix_path = package_path.join(PACKAGE_NIX_FILENAME);
         let result = result.and_(if !package_nix_path.exists() {
-            npv_143::PackageNixMissing::new(package_name.clone()).into()
+            npv_143::PackageNixMissing::new(relative_package_dir.clone()).into()
         } else if !package_nix_path.is_file() {
-            npv_144::PackageNixIsNotFile::new(package_name.clone()).into()
+            npv_144::PackageNixIsNotFile::new(relative_package_dir.clone()).into()
         } else {
             Success(())
         });
 
-        let result = result.and_(references::check_references(
-            nix_file_store,
-            &relative_package_dir,
-            &relative_package_dir.to_path(path),
-        )?);
+        let result = if config.runs(Category::References) {
+            result.and_(references::check_references(
+                nix_file_store,
+                config,
+                &relative_package_dir,
+                &relative_package_dir.to_path(path),
+            )?)
+        } else {
+            result
+        };
 
-        result.map(|_| package_name)
+        config.filter(result).map(|_| package_name)
     })
 }
@@ -0,0 +1,254 @@
+//! Inline suppression comments in Nix files.
+//!
+//! A comment of the form
+//!
+//! ```nix
+//! # nixpkgs-vet: allow NPV-170 -- The regex needs this escape
+//! ```
+//!
+//! suppresses the listed problems for the syntax node directly following it. Multiple codes can
+//! be separated by commas. The reason after `--` is required, so that the justification is
+//! documented right next to the code.
+
+use std::cell::Cell;
+
+use rnix::{SyntaxKind, SyntaxNode};
+use rowan::TextRange;
+use rowan::ast::AstNode;
+
+/// The prefix every suppression comment starts with, after the comment delimiter.
+const DIRECTIVE_PREFIX: &str = "nixpkgs-vet:";
+
+/// The problem codes that can be suppressed inline, because their checks report a location
+/// within a Nix file.
+pub const SUPPRESSIBLE_CODES: &[&str] = &[
+    "NPV-121", "NPV-122", "NPV-123", "NPV-124", "NPV-127", "NPV-128", "NPV-134", "NPV-170",
+];
+
+/// A valid suppression comment.
+pub struct Suppression {
+    /// The string index of the comment, for error messages.
+    pub offset: usize,
+    /// The problem codes that are suppressed.
+    pub codes: Vec<String>,
+    /// The text range of the syntax node the suppression applies to.
+    range: TextRange,
+    /// Whether any problem was suppressed by this comment.
+    used: Cell<bool>,
+}
+
+/// A comment that looks like a suppression, but isn't valid.
+pub struct InvalidSuppression {
+    /// The string index of the comment, for error messages.
+    pub offset: usize,
+    /// The comment itself.
+    pub text: String,
+    /// Why the suppression is invalid.
+    pub reason: String,
+}
+
+/// All suppression comments of a Nix file.
+#[derive(Default)]
+pub struct Suppressions {
+    pub valid: Vec<Suppression>,
+    pub invalid: Vec<InvalidSuppression>,
+}
+
+impl Suppressions {
+    /// Collects all suppression comments in a parsed Nix file.
+    pub fn new(syntax_root: &rnix::Root) -> Suppressions {
+        let mut suppressions = Suppressions::default();
+
+        for token in syntax_root
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(rnix::SyntaxElement::into_token)
+            .filter(|token| token.kind() == SyntaxKind::TOKEN_COMMENT)
+        {
+            let Some(directive) = directive(token.text()) else {
+                continue;
+            };
+            let offset = token.text_range().start().into();
+            let invalid = |reason: &str| InvalidSuppression {
+                offset,
+                text: token.text().to_owned(),
+                reason: reason.to_owned(),
+            };
+
+            let Some(rest) = directive.strip_prefix("allow") else {
+                suppressions
+                    .invalid
+                    .push(invalid("Only `allow` directives are supported"));
+                continue;
+            };
+
+            let Some((codes, reason)) = rest.split_once("--") else {
+                suppressions
+                    .invalid
+                    .push(invalid("A reason is required after `--`"));
+                continue;
+            };
+
+            if reason.trim().is_empty() {
+                suppressions
+                    .invalid
+                    .push(invalid("A reason is required after `--`"));
+                continue;
+            }
+
+            let codes: Vec<String> = codes
+                .split(',')
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(str::to_owned)
+                .collect();
+
+            if codes.is_empty() {
+                suppressions
+                    .invalid
+                    .push(invalid("At least one problem code is required"));
+                continue;
+            }
+
+            if let Some(code) = codes
+                .iter()
+                .find(|code| !SUPPRESSIBLE_CODES.contains(&code.as_str()))
+            {
+                suppressions.invalid.push(invalid(&format!(
+                    "{code} cannot be suppressed inline, only {} can",
+                    SUPPRESSIBLE_CODES.join(", ")
+                )));
+                continue;
+            }
+
+            let Some(node) = next_node(&token) else {
+                suppressions
+                    .invalid
+                    .push(invalid("There is no expression after the comment"));
+                continue;
+            };
+
+            suppressions.valid.push(Suppression {
+                offset,
+                codes,
+                range: node.text_range(),
+                used: Cell::new(false),
+            });
+        }
+
+        suppressions
+    }
+
+    /// Whether a problem with the given code at the given string index is suppressed.
+    ///
+    /// This marks the matching suppressions as used, see `unused`.
+    pub fn suppresses(&self, code: &str, index: usize) -> bool {
+        let Ok(index) = u32::try_from(index) else {
+            return false;
+        };
+        let mut suppressed = false;
+        for suppression in &self.valid {
+            if suppression.range.contains(index.into())
+                && suppression.codes.iter().any(|c| c == code)
+            {
+                suppression.used.set(true);
+                suppressed = true;
+            }
+        }
+        suppressed
+    }
+
+    /// The suppressions that haven't suppressed any problem so far.
+    pub fn unused(&self) -> impl Iterator<Item = &Suppression> {
+        self.valid
+            .iter()
+            .filter(|suppression| !suppression.used.get())
+    }
+}
+
+/// Returns the directive of a suppression comment, or `None` if it's any other comment.
+fn directive(comment: &str) -> Option<&str> {
+    let content = if let Some(line) = comment.strip_prefix('#') {
+        line
+    } else {
+        comment.strip_prefix("/*")?.strip_suffix("*/")?
+    };
+    Some(content.trim().strip_prefix(DIRECTIVE_PREFIX)?.trim())
+}
+
+/// The syntax node following a token, skipping whitespace and other comments.
+fn next_node(token: &rnix::SyntaxToken) -> Option<SyntaxNode> {
+    let mut element = token.next_sibling_or_token();
+    while let Some(current) = element {
+        match current {
+            rnix::SyntaxElement::Node(node) => return Some(node),
+            rnix::SyntaxElement::Token(token) if token.kind().is_trivia() => {
+                element = token.next_sibling_or_token();
+            }
+            rnix::SyntaxElement::Token(_) => return None,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parses_suppressions() {
+        let contents = indoc! {r#"
+            {
+              # nixpkgs-vet: allow NPV-170 -- needed for the regex
+              a = "\.";
+              /* nixpkgs-vet: allow NPV-123, NPV-170 -- shared file */
+              b = ../.;
+              # nixpkgs-vet: allow NPV-170
+              c = "\.";
+              # nixpkgs-vet: allow NPV-100 -- not suppressible
+              d = null;
+              # nixpkgs-vet: deny NPV-170 -- unknown directive
+              e = null;
+              # Just a regular comment
+              f = null;
+              # nixpkgs-vet: allow NPV-170 -- nothing follows
+            }
+        "#};
+        let syntax_root = rnix::Root::parse(contents).ok().expect("parses");
+        let suppressions = Suppressions::new(&syntax_root);
+
+        let valid: Vec<_> = suppressions
+            .valid
+            .iter()
+            .map(|suppression| suppression.codes.join(","))
+            .collect();
+        assert_eq!(valid, ["NPV-170", "NPV-123,NPV-170"]);
+
+        let invalid: Vec<_> = suppressions
+            .invalid
+            .iter()
+            .map(|suppression| suppression.reason.as_str())
+            .collect();
+        assert_eq!(
+            invalid,
+            [
+                "A reason is required after `--`",
+                "NPV-100 cannot be suppressed inline, only NPV-121, NPV-122, NPV-123, NPV-124, \
+                 NPV-127, NPV-128, NPV-134, NPV-170 can",
+                "Only `allow` directives are supported",
+                "There is no expression after the comment",
+            ]
+        );
+
+        let escape = contents.find(r"\.").expect("escape");
+        assert!(suppressions.suppresses("NPV-170", escape));
+        assert!(!suppressions.suppresses("NPV-123", escape));
+
+        let unused: Vec<_> = suppressions
+            .unused()
+            .map(|suppression| suppression.codes.join(","))
+            .collect();
+        assert_eq!(unused, ["NPV-123,NPV-170"]);
+    }
+}
@@ -1,10 +1,6 @@
 use crate::problem::Problem;
-use Validation::{Failure, Success};
+use Validation::{Failure, Success, SuccessWithWarnings};
 use itertools::concat;
-use itertools::{
-    Either::{Left, Right},
-    Itertools,
-};
 
 /// The validation result of a check.  Instead of exiting at the first failure, this type can
 /// accumulate multiple failures.  This can be achieved using the functions `and`, `sequence` and
@@ -12,14 +8,17 @@ use itertools::{
 ///
 /// This leans on <https://hackage.haskell.org/package/validation>.
 pub enum Validation<A> {
+    /// At least one of the problems is an error. Problems of lower severity are kept in order.
     Failure(Vec<Problem>),
     Success(A),
+    /// Successful, but with problems that aren't errors, like warnings or notes.
+    SuccessWithWarnings(A, Vec<Problem>),
 }
 
-impl<A, P: Into<Problem>> From<P> for Validation<A> {
-    /// Create a `Validation<A>` from a single check problem
+impl<P: Into<Problem>> From<P> for Validation<()> {
+    /// Create a `Validation<()>` from a single check problem, which only fails if it's an error
     fn from(value: P) -> Self {
-        Failure(vec![value.into()])
+        with_problems(vec![value.into()])
     }
 }
 
@@ -33,6 +32,9 @@ impl<A, P: Into<Problem>> From<P> for Validation<A> {
 ///   Further checks can be run even with this result type.
 ///   Such problems can be fixed by changing the Nixpkgs files.
 ///
+/// - Ok(SuccessWithWarnings(A, Vec<Problem>)): Like `Success`, but some problems that aren't
+///   errors have been found, which should be reported but don't fail the validation.
+///
 /// - Ok(Success(A)): A successful (potentially intermediate) result with an arbitrary value.
 ///   No fatal errors have occurred and no validation problems have been found with Nixpkgs.
 ///
@@ -59,6 +61,7 @@ impl<A> Validation<A> {
         match self {
             Failure(err) => Failure(err),
             Success(value) => Success(f(value)),
+            SuccessWithWarnings(value, warnings) => SuccessWithWarnings(f(value), warnings),
         }
     }
 
@@ -68,6 +71,17 @@ impl<A> Validation<A> {
         match self {
             Failure(err) => Ok(Failure(err)),
             Success(value) => f(value),
+            SuccessWithWarnings(value, warnings) => Ok(with_problems(warnings).and_(f(value)?)),
+        }
+    }
+
+    /// Split the validation into either the successful value along with its non-error problems,
+    /// or all the problems in case of a failure.
+    pub fn into_result(self) -> std::result::Result<(A, Vec<Problem>), Vec<Problem>> {
+        match self {
+            Failure(problems) => Err(problems),
+            Success(value) => Ok((value, vec![])),
+            SuccessWithWarnings(value, warnings) => Ok((value, warnings)),
         }
     }
 }
@@ -76,7 +90,17 @@ impl Validation<()> {
     /// Combine two validations, both of which need to be successful for the return value to be
     /// successful. The `Problem`s of both sides are returned concatenated.
     pub fn and_<B>(self, other: Validation<B>) -> Validation<B> {
-        self.and(other, |(), b| b)
+        self.normalize().and(other, |(), b| b)
+    }
+
+    /// Makes sure that a `Failure` contains at least one error. Individual problems are already
+    /// turned into a validation depending on their severity, but `Failure`s could also be
+    /// constructed directly.
+    fn normalize(self) -> Validation<()> {
+        match self {
+            Failure(problems) => with_problems(problems),
+            validation => validation,
+        }
     }
 }
 
@@ -84,14 +108,29 @@ impl<A> Validation<A> {
     /// Combine two validations, both of which need to be successful for the return value to be
     /// successful. The `Problem`s of both sides are returned concatenated.
     pub fn and<B, C, F: FnOnce(A, B) -> C>(self, other: Validation<B>, f: F) -> Validation<C> {
-        match (self, other) {
-            (Success(a), Success(b)) => Success(f(a, b)),
-            (Failure(errors_l), Failure(errors_r)) => Failure(concat([errors_l, errors_r])),
-            (Failure(errors), Success(_)) | (Success(_), Failure(errors)) => Failure(errors),
+        match (self.into_result(), other.into_result()) {
+            (Ok((a, warnings_l)), Ok((b, warnings_r))) => {
+                with_problems(concat([warnings_l, warnings_r])).map(|()| f(a, b))
+            }
+            (Err(problems_l), Err(problems_r)) => Failure(concat([problems_l, problems_r])),
+            (Err(problems_l), Ok((_, warnings_r))) => Failure(concat([problems_l, warnings_r])),
+            (Ok((_, warnings_l)), Err(problems_r)) => Failure(concat([warnings_l, problems_r])),
         }
     }
 }
 
+/// Turns a list of problems into a validation, which only fails if at least one of them is an
+/// error.
+pub fn with_problems(problems: Vec<Problem>) -> Validation<()> {
+    if problems.is_empty() {
+        Success(())
+    } else if problems.iter().any(Problem::is_error) {
+        Failure(problems)
+    } else {
+        SuccessWithWarnings((), problems)
+    }
+}
+
 /// Combine many validations into a single one.
 ///
 /// All given validations need to be successful in order for the returned validation to be
@@ -100,26 +139,70 @@ impl<A> Validation<A> {
 ///
 /// Otherwise, the `Problem`s of all validations are returned concatenated.
 pub fn sequence<A>(check_results: impl IntoIterator<Item = Validation<A>>) -> Validation<Vec<A>> {
-    let (errors, values): (Vec<Vec<Problem>>, Vec<A>) =
-        check_results
-            .into_iter()
-            .partition_map(|validation| match validation {
-                Failure(err) => Left(err),
-                Success(value) => Right(value),
-            });
-
-    // To combine the errors from the results we flatten all the error Vec's into a new Vec
-    // This is not very efficient, but doesn't matter because generally we should have no errors
-    let flattened_errors = errors.into_iter().concat();
-
-    if flattened_errors.is_empty() {
-        Success(values)
+    let mut problems = vec![];
+    let mut failed = false;
+    let values: Vec<A> = check_results
+        .into_iter()
+        .filter_map(|validation| match validation {
+            Failure(err) => {
+                failed = true;
+                problems.extend(err);
+                None
+            }
+            Success(value) => Some(value),
+            SuccessWithWarnings(value, warnings) => {
+                problems.extend(warnings);
+                Some(value)
+            }
+        })
+        .collect();
+
+    if failed {
+        Failure(problems)
     } else {
-        Failure(flattened_errors)
+        with_problems(problems).map(|()| values)
     }
 }
 
 /// Like `sequence`, but without any containing value, for convenience
 pub fn sequence_(validations: impl IntoIterator<Item = Validation<()>>) -> Validation<()> {
-    sequence(validations).map(|_| ())
+    sequence(validations.into_iter().map(Validation::normalize)).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::{npv_130, npv_140};
+
+    fn warning() -> Validation<()> {
+        npv_130::PackageContainsUnusedFile::new("pkgs/by-name/fo/foo", "unused.txt").into()
+    }
+
+    fn error() -> Validation<()> {
+        npv_140::PackageDirectoryIsNotDirectory::new("pkgs/by-name/fo/foo").into()
+    }
+
+    #[test]
+    fn problems_depend_on_severity() {
+        assert!(matches!(warning(), SuccessWithWarnings((), problems) if problems.len() == 1));
+        assert!(matches!(error(), Failure(problems) if problems.len() == 1));
+
+        assert!(matches!(
+            warning().and_(warning()),
+            SuccessWithWarnings((), problems) if problems.len() == 2
+        ));
+        assert!(matches!(
+            warning().and_(Success(())),
+            SuccessWithWarnings((), _)
+        ));
+        assert!(matches!(
+            Success(()).and_(warning()),
+            SuccessWithWarnings((), _)
+        ));
+        assert!(matches!(warning().and_(error()), Failure(problems) if problems.len() == 2));
+        assert!(matches!(
+            sequence_([warning(), Success(()), warning()]),
+            SuccessWithWarnings((), problems) if problems.len() == 2
+        ));
+    }
 }
@@ -0,0 +1 @@
+self: super: { someAlias = self.someDrv; }
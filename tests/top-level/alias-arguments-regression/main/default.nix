@@ -0,0 +1 @@
+import <test-nixpkgs> { root = ./.; }
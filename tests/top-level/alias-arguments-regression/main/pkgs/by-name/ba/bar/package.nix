@@ -0,0 +1,3 @@
+{ someDrv, someAlias }:
+assert someAlias != null;
+someDrv
@@ -0,0 +1 @@
+{ someDrv, enableBar ? false }: if enableBar then someDrv else someDrv
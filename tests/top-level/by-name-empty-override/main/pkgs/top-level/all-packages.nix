@@ -0,0 +1,5 @@
+self: super: {
+  foo = self.callPackage ../by-name/fo/foo/package.nix { };
+
+  bar = self.callPackage ../by-name/ba/bar/package.nix { enableBar = true; };
+}
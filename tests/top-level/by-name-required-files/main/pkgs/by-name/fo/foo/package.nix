@@ -0,0 +1 @@
+{ someDrv }: someDrv
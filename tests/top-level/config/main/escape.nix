@@ -0,0 +1,2 @@
+# This escape is not reported, because NPV-170 is disabled
+builtins.seq " \. " null
@@ -0,0 +1,3 @@
+# This directory is ignored, so the invalid suppression is not reported
+# nixpkgs-vet: allow NPV-170
+null
@@ -0,0 +1,4 @@
+{ lib, stdenv }:
+{
+  meta.broken = stdenv.hostPlatform.isDarwin;
+}
@@ -0,0 +1,4 @@
+{ lib, stdenv }:
+{
+  meta.license = lib.licenses.gpl3;
+}
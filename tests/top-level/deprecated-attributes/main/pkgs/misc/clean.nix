@@ -0,0 +1,5 @@
+{ lib, stdenv }:
+{
+  meta.broken = stdenv.isDarwin;
+  meta.platforms = stdenv.lib.platforms.unix;
+}
@@ -0,0 +1,4 @@
+{ substituteAll }:
+substituteAll {
+  src = ./config.h.in;
+}
@@ -0,0 +1,5 @@
+{ lib, stdenv }:
+{
+  meta.license = lib.licenses.gpl3;
+  meta.broken = stdenv.isDarwin;
+}
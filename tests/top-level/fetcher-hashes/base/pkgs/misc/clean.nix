@@ -0,0 +1,7 @@
+{ fetchFromGitHub }:
+fetchFromGitHub {
+  owner = "foo";
+  repo = "clean";
+  rev = "v1.0";
+  hash = "sha256-2gP0vWEHEnLf3+uTsKwWOn71d5Hf0uWp1mj6cVaNgAg=";
+}
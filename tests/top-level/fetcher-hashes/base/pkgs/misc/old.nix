@@ -0,0 +1,5 @@
+{ fetchurl }:
+fetchurl {
+  url = "https://example.org/old-1.0.tar.gz";
+  sha256 = "1hv6zp2pq6qgs0n7bpjm6f5djf4ihrvqbsbrq4b3a0kg3fcbw8zy";
+}
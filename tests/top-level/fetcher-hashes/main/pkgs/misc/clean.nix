@@ -0,0 +1,7 @@
+{ lib, fetchFromGitHub }:
+fetchFromGitHub {
+  owner = "foo";
+  repo = "clean";
+  rev = "v2.0";
+  hash = lib.fakeHash;
+}
@@ -0,0 +1,5 @@
+{ fetchzip }:
+fetchzip {
+  url = "https://example.org/new-1.0.zip";
+  md5 = "d41d8cd98f00b204e9800998ecf8427e";
+}
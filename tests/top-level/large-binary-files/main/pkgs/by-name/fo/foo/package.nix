@@ -0,0 +1,8 @@
+{ someDrv }:
+someDrv
+// {
+  src = ./foo-1.0.tar.gz;
+  icon = ./icon.bin;
+  patches = [ ./large.patch ];
+  notes = ./notes.txt;
+}
@@ -1,4 +1,5 @@
 self: super: {
   foo1 = self.callPackage ({ someDrv }: someDrv) { };
   foo2 = self.callPackage ./../../without-config.nix { };
+  foo3 = self.callPackage ./../../without-config.nix ({ } // { });
 }
@@ -0,0 +1 @@
+{ someDrv }: someDrv.overrideAttrs (_: _: { name = "baz-1.0"; })
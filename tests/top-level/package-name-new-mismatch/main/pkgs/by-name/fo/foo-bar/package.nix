@@ -0,0 +1 @@
+{ someDrv }: someDrv.overrideAttrs (_: _: { pname = "Foo_Bar"; })
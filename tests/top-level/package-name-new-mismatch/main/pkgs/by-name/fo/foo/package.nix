@@ -0,0 +1 @@
+{ someDrv }: someDrv.overrideAttrs (_: _: { pname = "foo-cli"; })
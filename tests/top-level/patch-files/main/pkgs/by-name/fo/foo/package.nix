@@ -0,0 +1,14 @@
+{ someDrv }:
+someDrv
+// {
+  patches = [
+    ./valid.patch
+    ./combined.patch
+    ./binary.patch
+    ./empty.patch
+    ./truncated.patch
+    ./bad-header.diff
+    ./store-path.patch
+    ./line-endings.patch
+  ];
+}
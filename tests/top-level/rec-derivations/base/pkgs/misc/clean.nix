@@ -0,0 +1,5 @@
+{ stdenv }:
+stdenv.mkDerivation (finalAttrs: {
+  pname = "clean";
+  version = "1.0";
+})
@@ -0,0 +1,6 @@
+{ stdenv }:
+stdenv.mkDerivation rec {
+  pname = "old";
+  version = "1.0";
+  name = "${pname}-${version}";
+}
@@ -0,0 +1,5 @@
+{ stdenv }:
+stdenv.mkDerivation rec {
+  pname = "clean";
+  version = "1.0";
+}
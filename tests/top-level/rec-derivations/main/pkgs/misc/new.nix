@@ -0,0 +1,5 @@
+{ buildPythonPackage }:
+buildPythonPackage rec {
+  pname = "new";
+  version = "1.0";
+}
@@ -0,0 +1,7 @@
+{ someDrv }:
+someDrv
+// {
+  data = ./. + "/data.txt";
+  concatenated = ./. + "/..";
+  sibling = ./. + "foo";
+}
@@ -0,0 +1,7 @@
+{ someDrv }:
+someDrv
+// {
+  helper = import ./helper.in;
+  data = builtins.path { path = ./data; };
+  patches = [ ./fix-build.patch ];
+}
@@ -0,0 +1,2 @@
+# This escape is only reported as a warning
+builtins.seq " \. " null
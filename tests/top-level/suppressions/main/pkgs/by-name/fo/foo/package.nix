@@ -0,0 +1,14 @@
+{ someDrv }:
+someDrv
+// {
+  # nixpkgs-vet: allow NPV-170 -- Matches the upstream regex verbatim
+  regex = "\.";
+  # nixpkgs-vet: allow NPV-123 -- The parent directory is intentionally referenced
+  parent = ../.;
+  # nixpkgs-vet: allow NPV-123 -- Nothing to suppress here
+  local = ./.;
+  # nixpkgs-vet: allow NPV-170
+  slash = "\/";
+  grandparent = ../..;
+  helper = import ./helper.in;
+}
@@ -0,0 +1 @@
+{ someDrv }: someDrv // { extra = import ./extra.nix; }
@@ -0,0 +1,5 @@
+{ someDrv }:
+let
+  version = "1.0"
+in
+someDrv // { inherit version; }
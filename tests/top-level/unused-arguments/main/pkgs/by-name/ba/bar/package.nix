@@ -0,0 +1,2 @@
+{ someDrv, selectedArg, unselectedArg, ... }@args:
+someDrv // { selected = args.selectedArg; }
@@ -0,0 +1,2 @@
+{ someDrv, passedArg, ... }@args:
+someDrv // { passed = builtins.attrNames args; }
@@ -0,0 +1,19 @@
+{
+  someDrv,
+  inheritedArg,
+  defaultArg,
+  withDefault ? defaultArg,
+  shadowedArg,
+  letShadowedArg,
+  unusedArg,
+  # nixpkgs-vet: allow NPV-134 -- Kept for compatibility with overrides
+  keptArg,
+}:
+let
+  letShadowedArg = null;
+in
+someDrv
+// {
+  inherit inheritedArg withDefault letShadowedArg;
+  shadowed = shadowedArg: shadowedArg;
+}